@@ -0,0 +1,27 @@
+use bevy::prelude::*;
+use boids_rs::{testing::TestFlock, WorldBounds};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+// side of the square the flock spreads over, about the density of the demo window
+const AREA_SIZE: f32 = 8000.;
+
+// one fixed tick of the whole simulation, has to stay under 16.7 ms for 60 Hz
+fn flock_tick(c: &mut Criterion) {
+    let mut group = c.benchmark_group("flock_tick");
+    group.sample_size(20);
+
+    for count in [1_000, 10_000] {
+        let bounds = WorldBounds::from_size(Vec2::splat(AREA_SIZE));
+        let mut flock = TestFlock::new(0).with_bounds(bounds);
+        flock.spawn_random(count, bounds.0);
+        // let the flock form groups before measuring
+        flock.step(60);
+
+        group.bench_function(format!("{count}_boids"), |b| b.iter(|| flock.step(1)));
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, flock_tick);
+criterion_main!(benches);
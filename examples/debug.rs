@@ -1,26 +1,13 @@
 use bevy::{
-    app::{App, Startup, Update},
-    asset::Assets,
-    core_pipeline::core_2d::Camera2dBundle,
-    ecs::{
-        component::Component,
-        entity::Entity,
-        query::With,
-        system::{Commands, Query, Res, ResMut},
-    },
-    gizmos::gizmos::Gizmos,
-    input::{keyboard::KeyCode, ButtonInput},
-    math::{primitives::Circle, Vec3},
+    app::AppExit,
+    color::palettes::{basic, css},
     prelude::*,
-    render::{camera::Camera, color::Color, mesh::Mesh},
-    sprite::{ColorMaterial, MaterialMesh2dBundle},
-    transform::components::{GlobalTransform, Transform},
-    utils::default,
-    window::{close_on_esc, Window},
-    DefaultPlugins,
 };
 
-use boids_rs::{AlignmentRule, BoidMovement, CohesionRule, RulesPlugin, SeparationRule};
+use boids_rs::{
+    draw_vision_cone, AlignmentRule, BoidBundle, BoidMovement, BoidSpawn, CohesionRule, Cursor,
+    CursorPlugin, RuleParams, RulesPlugin, SeparationRule,
+};
 
 #[derive(States, Default, Clone, Copy, Eq, PartialEq, Hash, Debug)]
 pub enum RuleState {
@@ -31,11 +18,6 @@ pub enum RuleState {
     Combined,
 }
 
-#[derive(Resource)]
-struct Cursor {
-    pos: Vec2,
-}
-
 fn separation_enabled(state: Res<State<RuleState>>) -> bool {
     matches!(**state, RuleState::Separation | RuleState::Combined)
 }
@@ -67,17 +49,13 @@ struct VelocityDebugText;
 fn main() {
     App::new()
         .add_plugins(DefaultPlugins)
-        .add_plugins(RulesPlugin)
+        .add_plugins((RulesPlugin, CursorPlugin))
         .add_systems(Startup, setup)
-        .insert_resource(Cursor {
-            pos: Vec2::new(0., 0.),
-        })
         .init_state::<RuleState>()
         .add_systems(Update, state_change_system)
         .add_systems(
             PostUpdate,
             (
-                cursor_system,
                 cursor_gizmo_system,
                 radius_gizmo_system,
                 state_text_system,
@@ -93,7 +71,7 @@ fn main() {
             )
                 .chain(),
         )
-        .run()
+        .run();
 }
 
 fn setup(
@@ -101,74 +79,55 @@ fn setup(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
-    commands.spawn(Camera2dBundle::default());
+    commands.spawn(Camera2d);
     commands.spawn((
-        TextBundle::from_section(
-            "",
-            TextStyle {
-                font_size: 26.0,
-                ..default()
-            },
-        )
-        .with_style(Style {
+        Text::new(""),
+        TextFont {
+            font_size: 26.0,
+            ..default()
+        },
+        Node {
             position_type: PositionType::Absolute,
             bottom: Val::Px(10.0),
             left: Val::Px(10.0),
             ..default()
-        }),
+        },
         ControlsText,
     ));
 
     commands.spawn((
-        TextBundle::from_section(
-            "",
-            TextStyle {
-                font_size: 26.0,
-                ..default()
-            },
-        )
-        .with_style(Style {
+        Text::new(""),
+        TextFont {
+            font_size: 26.0,
+            ..default()
+        },
+        Node {
             position_type: PositionType::Absolute,
             top: Val::Px(10.0),
             left: Val::Px(10.0),
             ..default()
-        }),
-        VelocityDebugText,
-    ));
-
-    commands.spawn((
-        MaterialMesh2dBundle {
-            mesh: meshes.add(Circle::new(3.)).into(),
-            material: materials.add(ColorMaterial::from(Color::WHITE)),
-            ..default()
         },
-        SeparationRule::new(TARGET_BOID_ID, RULES_RADIUS * 0.5, 1., Vec2::ZERO),
-        AlignmentRule::new(TARGET_BOID_ID, RULES_RADIUS * 1.0, 1., Vec2::ZERO),
-        CohesionRule::new(TARGET_BOID_ID, RULES_RADIUS * 0.75, 1., Vec2::ZERO),
-        BoidMovement::new(TARGET_BOID_ID, 90., 0., std::f32::consts::PI),
+        VelocityDebugText,
     ));
-}
-
-fn cursor_system(
-    window_query: Query<&Window>,
-    camera_query: Query<(&Camera, &GlobalTransform)>,
-    mut cursor: ResMut<Cursor>,
-) {
-    let (camera, camera_transform) = camera_query.single();
-
-    let Some(cursor_pos) = window_query.single().cursor_position() else {
-        return;
-    };
 
-    let Some(point) = camera.viewport_to_world_2d(camera_transform, cursor_pos) else {
-        return;
+    let target = BoidSpawn {
+        speed: 90.,
+        rotation_speed: std::f32::consts::PI,
+        separation: RuleParams::new(RULES_RADIUS * 0.5, 1.),
+        alignment: RuleParams::new(RULES_RADIUS * 1.0, 1.),
+        cohesion: RuleParams::new(RULES_RADIUS * 0.75, 1.),
+        ..BoidSpawn::new(Vec2::ZERO, 0.)
     };
 
-    cursor.pos = point;
+    commands.spawn((
+        BoidBundle::new(TARGET_BOID_ID, &target),
+        Mesh2d(meshes.add(Circle::new(3.))),
+        MeshMaterial2d(materials.add(ColorMaterial::from(Color::WHITE))),
+    ));
 }
 
 fn cursor_gizmo_system(mut gizmos: Gizmos, cursor: Res<Cursor>) {
-    gizmos.circle_2d(cursor.pos, 5., Color::ANTIQUE_WHITE);
+    gizmos.circle_2d(cursor.pos, 5., css::ANTIQUE_WHITE);
 }
 
 fn radius_gizmo_system(
@@ -185,10 +144,15 @@ fn radius_gizmo_system(
 ) {
     let (transform, separation, alignment, cohesion) = query.single();
     let target_center = transform.translation().xy();
+    let heading = transform.up().xy();
 
-    gizmos.circle_2d(target_center, separation.radius, Color::RED);
-    gizmos.circle_2d(target_center, alignment.radius, Color::GREEN);
-    gizmos.circle_2d(target_center, cohesion.radius, Color::CYAN);
+    for (radius, fov, color) in [
+        (separation.radius, separation.fov, basic::RED),
+        (alignment.radius, alignment.fov, basic::LIME),
+        (cohesion.radius, cohesion.fov, basic::AQUA),
+    ] {
+        draw_vision_cone(&mut gizmos, target_center, heading, radius, fov, color);
+    }
 }
 
 fn clear_objects_system(
@@ -244,18 +208,19 @@ fn object_spawn_system(
 ) {
     if key_input.just_pressed(KeyCode::KeyG) {
         let direction_degrees = (fastrand::f32() * 360.0).to_radians();
+        let nearby = BoidSpawn {
+            speed: 90.,
+            rotation_speed: std::f32::consts::PI,
+            separation: RuleParams::new(RULES_RADIUS, 1.),
+            alignment: RuleParams::new(RULES_RADIUS, 1.),
+            cohesion: RuleParams::new(RULES_RADIUS, 1.),
+            ..BoidSpawn::new(cursor.pos, direction_degrees)
+        };
+
         commands.spawn((
-            MaterialMesh2dBundle {
-                mesh: meshes.add(Circle::new(5.)).into(),
-                material: materials.add(ColorMaterial::from(Color::SEA_GREEN)),
-                transform: Transform::from_translation(Vec3::new(cursor.pos.x, cursor.pos.y, 0.))
-                    .with_rotation(Quat::from_rotation_z(direction_degrees)),
-                ..default()
-            },
-            SeparationRule::new(1, RULES_RADIUS, 1., Vec2::ZERO),
-            AlignmentRule::new(1, RULES_RADIUS, 1., Vec2::ZERO),
-            CohesionRule::new(1, RULES_RADIUS, 1., Vec2::ZERO),
-            BoidMovement::new(1, 90., direction_degrees, std::f32::consts::PI),
+            BoidBundle::new(1, &nearby),
+            Mesh2d(meshes.add(Circle::new(5.))),
+            MeshMaterial2d(materials.add(ColorMaterial::from(Color::from(css::SEA_GREEN)))),
             NearbyBoid,
         ));
     }
@@ -272,7 +237,7 @@ fn state_text_system(
 
     let mut text = query.single_mut();
     let (separation, alignment, cohesion) = target.single();
-    let text = &mut text.sections[0].value;
+    let text = &mut text.0;
     text.clear();
 
     text.push_str("Press (G) to add a boid\n");
@@ -303,7 +268,7 @@ fn velocity_debug_text_system(
 ) {
     let (separation, alignment, cohesion) = target.single();
     let mut text = query.single_mut();
-    let text = &mut text.sections[0].value;
+    let text = &mut text.0;
 
     text.clear();
     text.push_str(&format!("Separation V: {}\n", separation.velocity));
@@ -340,13 +305,13 @@ fn separation_system(
         let center = transform.translation.xy();
         gizmos.arrow_2d(
             center,
-            center + Vec2::from_angle(movement.target_angle) * movement.speed,
-            Color::LIME_GREEN,
+            center + movement.target_direction.xy() * movement.speed,
+            css::LIMEGREEN,
         );
-        gizmos.line_2d(target_center, center, Color::DARK_GREEN);
+        gizmos.line_2d(target_center, center, css::DARK_GREEN);
     }
 
-    gizmos.arrow_2d(target_center, separation.velocity, Color::BLUE);
+    gizmos.arrow_2d(target_center, separation.velocity.xy(), basic::BLUE);
 }
 
 fn alignment_system(
@@ -361,13 +326,13 @@ fn alignment_system(
         let center = transform.translation.xy();
         gizmos.arrow_2d(
             center,
-            center + Vec2::from_angle(movement.target_angle) * movement.speed,
-            Color::LIME_GREEN,
+            center + movement.target_direction.xy() * movement.speed,
+            css::LIMEGREEN,
         );
-        gizmos.line_2d(target_center, center, Color::DARK_GREEN);
+        gizmos.line_2d(target_center, center, css::DARK_GREEN);
     }
 
-    gizmos.arrow_2d(target_center, alignment.velocity, Color::BLUE);
+    gizmos.arrow_2d(target_center, alignment.velocity.xy(), basic::BLUE);
 }
 
 fn cohesion_system(
@@ -382,12 +347,12 @@ fn cohesion_system(
         let center = transform.translation.xy();
         gizmos.arrow_2d(
             center,
-            center + Vec2::from_angle(movement.target_angle) * movement.speed,
-            Color::LIME_GREEN,
+            center + movement.target_direction.xy() * movement.speed,
+            css::LIMEGREEN,
         );
     }
 
-    gizmos.arrow_2d(target_center, cohesion.velocity, Color::BLUE);
+    gizmos.arrow_2d(target_center, cohesion.velocity.xy(), basic::BLUE);
 }
 
 fn combined_rules_system(
@@ -405,11 +370,17 @@ fn combined_rules_system(
     let (target, separation, alignment, cohesion) = target.single();
     let target_center = target.translation().xy();
     let velocities = [separation.velocity, alignment.velocity, cohesion.velocity];
-    let velocity: Vec2 = velocities.iter().map(|v| v.normalize()).sum();
+    let velocity: Vec2 = velocities.iter().map(|v| v.xy().normalize()).sum();
 
     gizmos.arrow_2d(
         target_center,
         target_center + velocity.normalize() * 30.,
-        Color::PURPLE,
+        basic::PURPLE,
     );
 }
+
+fn close_on_esc(key_input: Res<ButtonInput<KeyCode>>, mut app_exit: EventWriter<AppExit>) {
+    if key_input.just_pressed(KeyCode::Escape) {
+        app_exit.send(AppExit::Success);
+    }
+}
@@ -0,0 +1,49 @@
+use bevy::prelude::*;
+
+use boids_rs::{
+    BoidSpace, BoundaryMode, FlockConfig, HuntStrategy, MovementPlugin, Obstacle, PredatorBundle,
+    RulesPlugin, StartupPlugin, WorldBounds,
+};
+
+// side of the cube the flock lives in
+const ARENA_SIZE: f32 = 1200.;
+
+fn main() {
+    App::new()
+        .insert_resource(Time::<Fixed>::from_hz(60.))
+        .insert_resource(BoidSpace::Volume { depth: ARENA_SIZE })
+        .insert_resource(WorldBounds::from_size(Vec2::splat(ARENA_SIZE)))
+        .insert_resource(FlockConfig {
+            boid_count: 300,
+            ..default()
+        })
+        .add_plugins(DefaultPlugins)
+        .add_plugins((StartupPlugin, MovementPlugin, RulesPlugin))
+        .add_systems(Startup, (spawn_predator, spawn_pillar))
+        .add_systems(Update, boundary_mode_system)
+        .run();
+}
+
+fn spawn_predator(mut commands: Commands) {
+    commands.spawn(PredatorBundle::new(
+        Vec2::splat(-ARENA_SIZE / 2.),
+        0.,
+        HuntStrategy::Nearest,
+    ));
+}
+
+// a column through the whole box
+fn spawn_pillar(mut commands: Commands) {
+    commands.spawn((Obstacle::circle(80.), Transform::from_xyz(200., 150., 0.)));
+}
+
+// B switches between wrapping around the box and bouncing off its walls
+fn boundary_mode_system(key_input: Res<ButtonInput<KeyCode>>, mut mode: ResMut<BoundaryMode>) {
+    if key_input.just_pressed(KeyCode::KeyB) {
+        *mode = match *mode {
+            BoundaryMode::Wrap => BoundaryMode::Bounce,
+            _ => BoundaryMode::Wrap,
+        };
+        info!("boundary mode: {:?}", *mode);
+    }
+}
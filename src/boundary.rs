@@ -0,0 +1,154 @@
+use bevy::prelude::*;
+
+use crate::{
+    turn_towards, Arena, BoidMovement, BoidSpace, FlockFilter, Velocity, Wall, WorldBounds,
+};
+
+/// What happens to boids at the edges of [`WorldBounds`].
+/// Can be changed at any time while the simulation runs.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Default)]
+pub enum BoundaryMode {
+    /// Boids leaving one edge come back in on the opposite one.
+    #[default]
+    Wrap,
+    /// Boids reflect off the walls.
+    Bounce,
+    /// Boids closer than `margin` to a wall are steered back towards the middle.
+    SteerAway { margin: f32 },
+    /// No edges, the camera follows the flock instead.
+    Open,
+}
+
+impl BoundaryMode {
+    /// Bends the blended rule `velocity` away from the sides of `arena` within `margin`,
+    /// fully inwards once a boid reaches one.
+    /// Other modes leave it untouched.
+    pub fn steer_away(&self, velocity: Vec3, position: Vec3, arena: Arena, speed: f32) -> Vec3 {
+        let BoundaryMode::SteerAway { margin } = *self else {
+            return velocity;
+        };
+
+        let inner = arena.inflate(-margin);
+        let push = (inner.min - position).max(Vec3::ZERO) - (position - inner.max).max(Vec3::ZERO);
+        let Some(inwards) = push.try_normalize() else {
+            return velocity;
+        };
+
+        let weight = (push.length() / margin).min(1.);
+        let direction = velocity
+            .normalize_or_zero()
+            .lerp(inwards, weight)
+            .normalize_or(inwards);
+
+        direction * velocity.length().max(speed)
+    }
+}
+
+pub(crate) fn boids_boundary_system(
+    mode: Res<BoundaryMode>,
+    bounds: Res<WorldBounds>,
+    space: Res<BoidSpace>,
+    mut query: Query<(&mut Transform, &mut BoidMovement, Option<&mut Velocity>)>,
+) {
+    let arena = space.arena(bounds.0);
+
+    match *mode {
+        BoundaryMode::Wrap => {
+            for (mut transform, _, _) in &mut query {
+                wrap(&mut transform, arena);
+            }
+        }
+        BoundaryMode::Bounce => {
+            for (mut transform, mut movement, velocity) in &mut query {
+                bounce(&mut transform, &mut movement, velocity, arena);
+            }
+        }
+        BoundaryMode::SteerAway { .. } | BoundaryMode::Open => (),
+    }
+}
+
+// An unbounded side, like Z in the plane, is never crossed.
+fn wrap(transform: &mut Transform, arena: Arena) {
+    for axis in 0..3 {
+        let position = transform.translation[axis];
+
+        if position > arena.max[axis] {
+            transform.translation[axis] = arena.min[axis];
+        } else if position < arena.min[axis] {
+            transform.translation[axis] = arena.max[axis];
+        }
+    }
+}
+
+fn bounce(
+    transform: &mut Transform,
+    movement: &mut BoidMovement,
+    velocity: Option<Mut<Velocity>>,
+    arena: Arena,
+) {
+    let center = transform.translation;
+    let heading = transform.rotation * Vec3::Y;
+
+    // only flip boids still moving outwards, so they cannot get stuck in a wall
+    let outwards = (center.cmpgt(arena.max) & heading.cmpgt(Vec3::ZERO))
+        | (center.cmplt(arena.min) & heading.cmplt(Vec3::ZERO));
+
+    if !outwards.any() {
+        return;
+    }
+
+    let flip = Vec3::select(outwards, Vec3::NEG_ONE, Vec3::ONE);
+
+    transform.translation = center.clamp(arena.min, arena.max);
+    transform.rotation = turn_towards(transform.rotation, heading * flip, f32::INFINITY);
+    movement.target_direction = heading * flip;
+
+    if let Some(mut velocity) = velocity {
+        velocity.0 *= flip;
+    }
+}
+
+// Walls only mean something while boids are kept inside them.
+pub(crate) fn boundary_walls_visibility_system(
+    mode: Res<BoundaryMode>,
+    mut wall_query: Query<&mut Visibility, With<Wall>>,
+) {
+    if !mode.is_changed() {
+        return;
+    }
+
+    let visibility = match *mode {
+        BoundaryMode::Open => Visibility::Hidden,
+        _ => Visibility::Inherited,
+    };
+
+    for mut wall_visibility in &mut wall_query {
+        *wall_visibility = visibility;
+    }
+}
+
+pub(crate) fn camera_follow_system(
+    mode: Res<BoundaryMode>,
+    time: Res<Time>,
+    boid_query: Query<&Transform, (FlockFilter, Without<Camera2d>)>,
+    mut camera_query: Query<&mut Transform, With<Camera2d>>,
+) {
+    const FOLLOW_RATE: f32 = 2.;
+
+    let Ok(mut camera) = camera_query.get_single_mut() else {
+        return;
+    };
+
+    let target = match *mode {
+        BoundaryMode::Open if !boid_query.is_empty() => {
+            let sum: Vec2 = boid_query.iter().map(|t| t.translation.xy()).sum();
+            sum / boid_query.iter().len() as f32
+        }
+        _ => Vec2::ZERO,
+    };
+
+    let position = camera.translation.xy();
+    let followed = position.lerp(target, (FOLLOW_RATE * time.delta_secs()).min(1.));
+    camera.translation.x = followed.x;
+    camera.translation.y = followed.y;
+}
@@ -0,0 +1,42 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+
+/// Adds the [`Cursor`] resource and keeps it up to date.
+pub struct CursorPlugin;
+
+impl Plugin for CursorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Cursor>();
+        app.add_systems(PreUpdate, cursor_system);
+    }
+}
+
+/// Cursor position in world coordinates.
+/// Keeps its last position while the cursor is outside the window.
+#[derive(Resource, Debug, Default)]
+pub struct Cursor {
+    pub pos: Vec2,
+}
+
+fn cursor_system(
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    mut cursor: ResMut<Cursor>,
+) {
+    let Ok((camera, camera_transform)) = camera_query.get_single() else {
+        return;
+    };
+
+    let Some(cursor_pos) = window_query
+        .get_single()
+        .ok()
+        .and_then(Window::cursor_position)
+    else {
+        return;
+    };
+
+    let Ok(point) = camera.viewport_to_world_2d(camera_transform, cursor_pos) else {
+        return;
+    };
+
+    cursor.pos = point;
+}
@@ -0,0 +1,137 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::{BoidSpawn, RuleParams};
+
+/// Where a per-boid parameter is drawn from when the flock is spawned.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Distribution {
+    Fixed(f32),
+    Uniform {
+        min: f32,
+        max: f32,
+    },
+    /// Gaussian, clamped so outliers cannot produce negative radii or speeds.
+    Normal {
+        mean: f32,
+        std_dev: f32,
+        min: f32,
+        max: f32,
+    },
+}
+
+impl Distribution {
+    /// Uniform between `a` and `b`, in either order.
+    pub fn uniform(a: f32, b: f32) -> Self {
+        Distribution::Uniform {
+            min: a.min(b),
+            max: a.max(b),
+        }
+    }
+
+    /// Gaussian clamped between `a` and `b`, in either order.
+    pub fn normal(mean: f32, std_dev: f32, a: f32, b: f32) -> Self {
+        Distribution::Normal {
+            mean,
+            std_dev,
+            min: a.min(b),
+            max: a.max(b),
+        }
+    }
+
+    pub fn sample(&self, rng: &mut fastrand::Rng) -> f32 {
+        match *self {
+            Distribution::Fixed(value) => value,
+            Distribution::Uniform { min, max } => min + (max - min) * rng.f32(),
+            Distribution::Normal {
+                mean,
+                std_dev,
+                min,
+                max,
+            } => {
+                // Box-Muller, 1 - f32() keeps the log argument away from 0
+                let radius = (-2. * (1. - rng.f32()).ln()).sqrt();
+                let z = radius * (TAU * rng.f32()).cos();
+                // bounds set the wrong way round would make `clamp` panic
+                (mean + std_dev * z).clamp(min.min(max), min.max(max))
+            }
+        }
+    }
+}
+
+/// Distribution of the movement and rule parameters across the flock.
+/// Everything not drawn here, like fields of view, neighbor selection, kinematic limits
+/// and the other rules, is copied from `template`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoidDistributions {
+    pub template: BoidSpawn,
+    pub speed: Distribution,
+    pub rotation_speed: Distribution,
+    pub separation_radius: Distribution,
+    pub separation_factor: Distribution,
+    pub alignment_radius: Distribution,
+    pub alignment_factor: Distribution,
+    pub cohesion_radius: Distribution,
+    pub cohesion_factor: Distribution,
+    pub wander_strength: Distribution,
+    pub wander_rate: Distribution,
+}
+
+impl Default for BoidDistributions {
+    fn default() -> Self {
+        Self::from_template(BoidSpawn::new(Vec2::ZERO, 0.))
+    }
+}
+
+impl BoidDistributions {
+    /// Every boid spawned like `template`, no parameter varies.
+    pub fn from_template(template: BoidSpawn) -> Self {
+        Self {
+            template,
+            speed: Distribution::Fixed(template.speed),
+            rotation_speed: Distribution::Fixed(template.rotation_speed),
+            separation_radius: Distribution::Fixed(template.separation.radius),
+            separation_factor: Distribution::Fixed(template.separation.factor),
+            alignment_radius: Distribution::Fixed(template.alignment.radius),
+            alignment_factor: Distribution::Fixed(template.alignment.factor),
+            cohesion_radius: Distribution::Fixed(template.cohesion.radius),
+            cohesion_factor: Distribution::Fixed(template.cohesion.factor),
+            wander_strength: Distribution::Fixed(template.wander_strength),
+            wander_rate: Distribution::Fixed(template.wander_rate),
+        }
+    }
+
+    /// A boid at `position` heading towards `heading` with freshly drawn parameters
+    /// and its own wander seed.
+    pub fn sample(&self, rng: &mut fastrand::Rng, position: Vec2, heading: f32) -> BoidSpawn {
+        let template = self.template;
+
+        BoidSpawn {
+            position,
+            heading,
+            speed: self.speed.sample(rng),
+            rotation_speed: self.rotation_speed.sample(rng),
+            separation: RuleParams {
+                radius: self.separation_radius.sample(rng),
+                factor: self.separation_factor.sample(rng),
+                ..template.separation
+            },
+            alignment: RuleParams {
+                radius: self.alignment_radius.sample(rng),
+                factor: self.alignment_factor.sample(rng),
+                ..template.alignment
+            },
+            cohesion: RuleParams {
+                radius: self.cohesion_radius.sample(rng),
+                factor: self.cohesion_factor.sample(rng),
+                ..template.cohesion
+            },
+            wander_strength: self.wander_strength.sample(rng),
+            wander_rate: self.wander_rate.sample(rng),
+            // every boid wanders its own way
+            wander_seed: rng.u64(..),
+            ..template
+        }
+    }
+}
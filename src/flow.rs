@@ -0,0 +1,150 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    image::{CompressedImageFormats, ImageSampler, ImageType, TextureError},
+    prelude::*,
+};
+
+/// Grid of drift vectors, in units per second, laid over `area`.
+/// Boids are carried along by the field on top of their own motion,
+/// insert it as a resource to model wind or currents.
+/// In a [`BoidSpace::Volume`](crate::BoidSpace) the drift is horizontal, the same at every depth.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct FlowField {
+    pub area: Rect,
+    cols: usize,
+    rows: usize,
+    // row by row, starting at the bottom left cell
+    vectors: Vec<Vec2>,
+}
+
+impl FlowField {
+    /// A field from `cols * rows` vectors, row by row from the bottom left cell.
+    pub fn new(area: Rect, cols: usize, rows: usize, vectors: Vec<Vec2>) -> Self {
+        assert!(cols > 0 && rows > 0, "a flow field needs at least one cell");
+        assert_eq!(vectors.len(), cols * rows, "expected one vector per cell");
+
+        Self {
+            area,
+            cols,
+            rows,
+            vectors,
+        }
+    }
+
+    /// A field with `flow` evaluated at the center of every cell.
+    pub fn from_fn(area: Rect, cols: usize, rows: usize, flow: impl Fn(Vec2) -> Vec2) -> Self {
+        let cell_size = area.size() / Vec2::new(cols as f32, rows as f32);
+        let vectors = (0..rows)
+            .flat_map(|row| (0..cols).map(move |col| (col, row)))
+            .map(|(col, row)| {
+                flow(area.min + (Vec2::new(col as f32, row as f32) + 0.5) * cell_size)
+            })
+            .collect();
+
+        Self::new(area, cols, rows, vectors)
+    }
+
+    /// The same `wind` everywhere in `area`.
+    pub fn uniform(area: Rect, wind: Vec2) -> Self {
+        Self::new(area, 1, 1, vec![wind])
+    }
+
+    /// Circles counter-clockwise around the center of `area`, `strength` fast at its edge
+    /// and calm in the middle, clockwise for a negative `strength`.
+    pub fn vortex(area: Rect, cells: usize, strength: f32) -> Self {
+        let center = area.center();
+        let radius = area.half_size().min_element();
+
+        Self::from_fn(area, cells, cells, |position| {
+            let offset = position - center;
+            offset.perp() / radius * strength
+        })
+    }
+
+    /// Flows along +X at the top of `area` and along -X at the bottom,
+    /// `strength` fast at the edges and still along the middle.
+    pub fn shear(area: Rect, cells: usize, strength: f32) -> Self {
+        let center = area.center();
+        let half_height = area.half_size().y;
+
+        Self::from_fn(area, 1, cells, |position| {
+            Vec2::X * (position.y - center.y) / half_height * strength
+        })
+    }
+
+    /// A field with one cell per pixel of `image`, the top row at the top of `area`.
+    /// The red and green channels encode the X and Y direction, 0.5 being still,
+    /// scaled to `strength` units per second.
+    pub fn from_image(image: &Image, area: Rect, strength: f32) -> Option<Self> {
+        let size = image.size();
+        let (cols, rows) = (size.x as usize, size.y as usize);
+
+        let mut vectors = Vec::with_capacity(cols * rows);
+        for y in (0..size.y).rev() {
+            for x in 0..size.x {
+                let color = image.get_color_at(x, y).ok()?.to_linear();
+                let direction = Vec2::new(color.red, color.green) * 2. - 1.;
+                vectors.push(direction * strength);
+            }
+        }
+
+        Some(Self::new(area, cols, rows, vectors))
+    }
+
+    /// Decodes a PNG, see [`FlowField::from_image`].
+    pub fn from_png(bytes: &[u8], area: Rect, strength: f32) -> Result<Self, TextureError> {
+        let image = Image::from_buffer(
+            bytes,
+            ImageType::Extension("png"),
+            CompressedImageFormats::NONE,
+            // the channels hold directions, not colors
+            false,
+            ImageSampler::Default,
+            RenderAssetUsages::MAIN_WORLD,
+        )?;
+
+        Self::from_image(&image, area, strength).ok_or_else(|| {
+            TextureError::UnsupportedTextureFormat(format!("{:?}", image.texture_descriptor.format))
+        })
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn get(&self, col: usize, row: usize) -> Vec2 {
+        self.vectors[row * self.cols + col]
+    }
+
+    pub fn set(&mut self, col: usize, row: usize, flow: Vec2) {
+        self.vectors[row * self.cols + col] = flow;
+    }
+
+    /// Drift at `position`, blended between the nearest cell centers.
+    /// Nothing drifts outside `area`.
+    pub fn sample(&self, position: Vec2) -> Vec2 {
+        if !self.area.contains(position) {
+            return Vec2::ZERO;
+        }
+
+        let max = Vec2::new(self.cols as f32 - 1., self.rows as f32 - 1.);
+        let cell = ((position - self.area.min) / self.area.size()
+            * Vec2::new(self.cols as f32, self.rows as f32)
+            - 0.5)
+            .clamp(Vec2::ZERO, max);
+
+        let (col, row) = (cell.x as usize, cell.y as usize);
+        let (next_col, next_row) = ((col + 1).min(self.cols - 1), (row + 1).min(self.rows - 1));
+        let t = cell - cell.floor();
+
+        let bottom = self.get(col, row).lerp(self.get(next_col, row), t.x);
+        let top = self
+            .get(col, next_row)
+            .lerp(self.get(next_col, next_row), t.x);
+        bottom.lerp(top, t.y)
+    }
+}
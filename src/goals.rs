@@ -0,0 +1,328 @@
+use bevy::{color::LinearRgba, prelude::*};
+
+use crate::{obstacle::closest_on_segment, BoidMovement, Velocity};
+
+// how far ahead, in seconds of flight, a path follower predicts its position
+const PATH_PREDICTION: f32 = 0.5;
+
+/// How the pull of an [`Attractor`] or push of a [`Repeller`] fades out towards its radius.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Falloff {
+    /// Full strength up to the radius.
+    Constant,
+    #[default]
+    Linear,
+    Quadratic,
+}
+
+impl Falloff {
+    /// Weight between 1 at the center and 0 at `radius`.
+    pub fn weight(&self, distance: f32, radius: f32) -> f32 {
+        if distance > radius || radius <= 0. {
+            return 0.;
+        }
+
+        let linear = (radius - distance) / radius;
+        match self {
+            Falloff::Constant => 1.,
+            Falloff::Linear => linear,
+            Falloff::Quadratic => linear * linear,
+        }
+    }
+}
+
+/// Pulls boids within `radius` towards it.
+#[derive(Component, Debug, Clone, Copy)]
+#[require(Transform)]
+pub struct Attractor {
+    pub strength: f32,
+    pub radius: f32,
+    pub falloff: Falloff,
+}
+
+impl Attractor {
+    pub fn new(strength: f32, radius: f32, falloff: Falloff) -> Self {
+        Self {
+            strength,
+            radius,
+            falloff,
+        }
+    }
+}
+
+/// Pushes boids within `radius` away from it.
+#[derive(Component, Debug, Clone, Copy)]
+#[require(Transform)]
+pub struct Repeller {
+    pub strength: f32,
+    pub radius: f32,
+    pub falloff: Falloff,
+}
+
+impl Repeller {
+    pub fn new(strength: f32, radius: f32, falloff: Falloff) -> Self {
+        Self {
+            strength,
+            radius,
+            falloff,
+        }
+    }
+}
+
+/// Sum of the pulls and pushes of all attractors and repellers on a boid.
+#[derive(Component)]
+pub struct AttractionRule {
+    // between 0.0 and 1.0
+    // 0 means off
+    pub factor: f32,
+    pub velocity: Vec3,
+}
+
+impl AttractionRule {
+    pub fn new(factor: f32, velocity: Vec3) -> Self {
+        Self { factor, velocity }
+    }
+}
+
+/// Steers a boid towards `goal`, slowing down within `arrive_radius`.
+/// Like the other goal rules its velocity is a steering force, the desired velocity
+/// minus the current one, so boids moving at constant speed circle the goal.
+/// Goals lie in the XY plane, in 3D the rule only steers across it.
+#[derive(Component, Debug, Clone)]
+pub struct SeekRule {
+    pub goal: Option<Vec2>,
+    // 0 seeks at full speed all the way
+    pub arrive_radius: f32,
+    // between 0.0 and 1.0
+    // 0 means off
+    pub factor: f32,
+    pub velocity: Vec3,
+}
+
+impl SeekRule {
+    pub fn new(goal: Vec2, arrive_radius: f32, factor: f32) -> Self {
+        Self {
+            goal: Some(goal),
+            arrive_radius,
+            factor,
+            velocity: Vec3::ZERO,
+        }
+    }
+}
+
+/// Steers a boid along a polyline of waypoints, staying within `radius` of it.
+/// Open paths end with an arrival at the last waypoint, looped ones go on forever.
+/// Like [`SeekRule`] it steers across the XY plane only.
+#[derive(Component, Debug, Clone)]
+pub struct PathFollowRule {
+    pub waypoints: Vec<Vec2>,
+    pub looped: bool,
+    pub radius: f32,
+    // between 0.0 and 1.0
+    // 0 means off
+    pub factor: f32,
+    pub velocity: Vec3,
+    // first waypoint of the segment the boid is on
+    segment: usize,
+}
+
+impl PathFollowRule {
+    pub fn new(waypoints: impl Into<Vec<Vec2>>, looped: bool, radius: f32, factor: f32) -> Self {
+        Self {
+            waypoints: waypoints.into(),
+            looped,
+            radius,
+            factor,
+            velocity: Vec3::ZERO,
+            segment: 0,
+        }
+    }
+
+    /// Index of the waypoint the boid is heading to.
+    pub fn next_waypoint(&self) -> usize {
+        (self.segment + 1) % self.waypoints.len().max(1)
+    }
+
+    fn segment_count(&self) -> usize {
+        match self.waypoints.len() {
+            0 | 1 => 0,
+            len if self.looped => len,
+            len => len - 1,
+        }
+    }
+
+    fn segment_points(&self, segment: usize) -> (Vec2, Vec2) {
+        let len = self.waypoints.len();
+        (
+            self.waypoints[segment % len],
+            self.waypoints[(segment + 1) % len],
+        )
+    }
+}
+
+// desired velocity towards `target`, slowed down inside `arrive_radius`, minus the current one
+pub(crate) fn seek(
+    position: Vec2,
+    velocity: Vec2,
+    target: Vec2,
+    speed: f32,
+    arrive_radius: f32,
+) -> Vec2 {
+    let offset = target - position;
+    let distance = offset.length();
+    let slowdown = if distance < arrive_radius {
+        distance / arrive_radius
+    } else {
+        1.
+    };
+
+    offset.normalize_or_zero() * speed * slowdown - velocity
+}
+
+pub(crate) fn current_velocity(
+    transform: &Transform,
+    movement: &BoidMovement,
+    velocity: Option<&Velocity>,
+) -> Vec2 {
+    velocity.map_or_else(
+        || (transform.rotation * Vec3::Y).xy() * movement.speed,
+        |velocity| velocity.xy(),
+    )
+}
+
+pub(crate) fn attraction_system(
+    attractors: Query<(&Attractor, &Transform)>,
+    repellers: Query<(&Repeller, &Transform)>,
+    mut boids: Query<(&Transform, &BoidMovement, &mut AttractionRule)>,
+) {
+    boids
+        .par_iter_mut()
+        .for_each(|(transform, movement, mut attraction)| {
+            let position = transform.translation;
+
+            let pull: Vec3 = attractors
+                .iter()
+                .map(|(attractor, attractor_transform)| {
+                    let offset = attractor_transform.translation - position;
+                    let weight = attractor.falloff.weight(offset.length(), attractor.radius);
+                    offset.normalize_or_zero() * attractor.strength * weight
+                })
+                .sum();
+
+            let push: Vec3 = repellers
+                .iter()
+                .map(|(repeller, repeller_transform)| {
+                    let offset = position - repeller_transform.translation;
+                    let weight = repeller.falloff.weight(offset.length(), repeller.radius);
+                    offset.normalize_or_zero() * repeller.strength * weight
+                })
+                .sum();
+
+            attraction.velocity = (pull + push) * movement.speed * attraction.factor;
+        });
+}
+
+pub(crate) fn seek_system(
+    mut boids: Query<(&Transform, &BoidMovement, Option<&Velocity>, &mut SeekRule)>,
+) {
+    for (transform, movement, velocity, mut seek_rule) in &mut boids {
+        let Some(goal) = seek_rule.goal else {
+            seek_rule.velocity = Vec3::ZERO;
+            continue;
+        };
+
+        seek_rule.velocity = seek(
+            transform.translation.xy(),
+            current_velocity(transform, movement, velocity),
+            goal,
+            movement.speed,
+            seek_rule.arrive_radius,
+        )
+        .extend(0.)
+            * seek_rule.factor;
+    }
+}
+
+// Reynolds' path following: predicts where the boid will be, projects that onto the
+// path and seeks a point further along it. Only the current and next segments are
+// searched, so paths crossing themselves are still followed in order.
+pub(crate) fn path_follow_system(
+    mut boids: Query<(
+        &Transform,
+        &BoidMovement,
+        Option<&Velocity>,
+        &mut PathFollowRule,
+    )>,
+) {
+    for (transform, movement, velocity, mut path) in &mut boids {
+        let segment_count = path.segment_count();
+        if segment_count == 0 {
+            path.velocity = Vec3::ZERO;
+            continue;
+        }
+
+        let position = transform.translation.xy();
+        let velocity = current_velocity(transform, movement, velocity);
+        let predicted = position + velocity * PATH_PREDICTION;
+
+        // the waypoints or `looped` may have changed since the last tick
+        path.segment = path.segment.min(segment_count - 1);
+        let last_segment = if path.looped {
+            path.segment + 1
+        } else {
+            (path.segment + 1).min(segment_count - 1)
+        };
+        let Some((segment, closest)) = (path.segment..=last_segment)
+            .map(|segment| {
+                let (start, end) = path.segment_points(segment);
+                (segment, closest_on_segment(start, end, predicted))
+            })
+            .min_by(|(_, a), (_, b)| {
+                a.distance_squared(predicted)
+                    .total_cmp(&b.distance_squared(predicted))
+            })
+        else {
+            path.velocity = Vec3::ZERO;
+            continue;
+        };
+        path.segment = segment % segment_count;
+
+        let (start, end) = path.segment_points(path.segment);
+        let arriving = !path.looped && path.segment == segment_count - 1;
+        let steering = if arriving {
+            seek(position, velocity, end, movement.speed, path.radius)
+        } else {
+            let target = closest + (end - start).normalize_or_zero() * path.radius;
+            seek(position, velocity, target, movement.speed, 0.)
+        };
+
+        path.velocity = steering.extend(0.) * path.factor;
+    }
+}
+
+pub(crate) fn goals_gizmo_system(
+    mut gizmos: Gizmos,
+    attractors: Query<(&Attractor, &Transform)>,
+    repellers: Query<(&Repeller, &Transform)>,
+    paths: Query<&PathFollowRule>,
+) {
+    for (attractor, transform) in &attractors {
+        gizmos.circle_2d(
+            transform.translation.xy(),
+            attractor.radius,
+            LinearRgba::GREEN,
+        );
+    }
+
+    for (repeller, transform) in &repellers {
+        gizmos.circle_2d(transform.translation.xy(), repeller.radius, LinearRgba::RED);
+    }
+
+    for path in &paths {
+        let closing = path.looped.then(|| path.waypoints.first()).flatten();
+        gizmos.linestrip_2d(
+            path.waypoints.iter().chain(closing).copied(),
+            LinearRgba::BLUE,
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use bevy::prelude::*;
+
+use crate::{turn_towards, BoidSpace, FlowField};
+
+/// How boids turn the blended rule velocity into motion.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MovementModel {
+    /// Boids fly at their constant `speed` and turn towards the rules at `rotation_speed`.
+    #[default]
+    ConstantSpeed,
+    /// Rule outputs act as forces on [`Velocity`], limited by [`KinematicLimits`].
+    /// Pairs with `BlendMode::Weighted` or `BlendMode::Prioritized`,
+    /// normalized rule directions are too weak to steer.
+    Physical,
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Default, Deref, DerefMut)]
+pub struct Velocity(pub Vec3);
+
+/// Steering force of the current tick, already clamped to `max_force`.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Default, Deref, DerefMut)]
+pub struct Acceleration(pub Vec3);
+
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct KinematicLimits {
+    pub max_force: f32,
+    pub min_speed: f32,
+    pub max_speed: f32,
+}
+
+impl KinematicLimits {
+    /// The speed limits may come in either order.
+    pub fn new(max_force: f32, min_speed: f32, max_speed: f32) -> Self {
+        Self {
+            max_force,
+            min_speed: min_speed.min(max_speed),
+            max_speed: min_speed.max(max_speed),
+        }
+    }
+}
+
+pub(crate) fn constant_speed_model(model: Res<MovementModel>) -> bool {
+    *model == MovementModel::ConstantSpeed
+}
+
+pub(crate) fn physical_model(model: Res<MovementModel>) -> bool {
+    *model == MovementModel::Physical
+}
+
+pub(crate) fn boids_physics_system(
+    time: Res<Time>,
+    space: Res<BoidSpace>,
+    flow: Option<Res<FlowField>>,
+    mut query: Query<(
+        &mut Transform,
+        &mut Velocity,
+        &Acceleration,
+        &KinematicLimits,
+    )>,
+) {
+    for (mut transform, mut velocity, acceleration, limits) in &mut query {
+        let heading = transform.rotation * Vec3::Y;
+        let new_velocity = space.flatten(velocity.0 + acceleration.0 * time.delta_secs());
+        // unlike `clamp` this takes limits edited out of order, `max_speed` wins
+        let speed = new_velocity
+            .length()
+            .max(limits.min_speed)
+            .min(limits.max_speed);
+
+        // a boid that braked to a halt keeps its heading
+        velocity.0 = new_velocity.try_normalize().unwrap_or(heading) * speed;
+
+        transform.translation += velocity.0 * time.delta_secs();
+        if let Some(flow) = &flow {
+            let drift = flow.sample(transform.translation.xy()) * time.delta_secs();
+            transform.translation += drift.extend(0.);
+        }
+        transform.rotation = turn_towards(transform.rotation, velocity.0, f32::INFINITY);
+    }
+}
@@ -0,0 +1,173 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+
+use crate::{
+    goals::{current_velocity, seek},
+    BoidMovement, NeighborIndex, SteeringVelocities, Velocity, BOID_SIZE,
+};
+
+// half the width of the corridor in front of a leader that followers clear
+const LEADER_PATH_WIDTH: f32 = BOID_SIZE * 2.;
+
+/// Marks a boid the flock around it follows.
+/// A leader still flocks like any other boid, turn its alignment and cohesion down
+/// to let its own steering lead: a [`PathFollowRule`](crate::PathFollowRule) or
+/// [`SeekRule`](crate::SeekRule) for waypoints, a [`LeaderScript`] or [`KeyboardSteering`].
+#[derive(Component, Debug, Default)]
+pub struct Leader;
+
+/// Steers a leader along the direction returned for the elapsed simulation time in seconds.
+#[derive(Component)]
+#[require(SteeringVelocities)]
+pub struct LeaderScript(pub Box<dyn Fn(f32) -> Vec2 + Send + Sync>);
+
+impl LeaderScript {
+    pub fn new(script: impl Fn(f32) -> Vec2 + Send + Sync + 'static) -> Self {
+        Self(Box::new(script))
+    }
+}
+
+/// Turns a leader while `left` or `right` is held, and keeps it straight otherwise.
+#[derive(Component, Debug)]
+#[require(SteeringVelocities)]
+pub struct KeyboardSteering {
+    pub left: KeyCode,
+    pub right: KeyCode,
+}
+
+impl Default for KeyboardSteering {
+    fn default() -> Self {
+        Self {
+            left: KeyCode::ArrowLeft,
+            right: KeyCode::ArrowRight,
+        }
+    }
+}
+
+/// Makes a boid within `radius` of a leader fall in `behind` it,
+/// and step aside when it is in the leader's way, steering across the XY plane.
+#[derive(Component)]
+pub struct FollowLeaderRule {
+    pub radius: f32,
+    // distance behind the leader to follow at
+    pub behind: f32,
+    // between 0.0 and 1.0
+    // 0 means off
+    pub factor: f32,
+    pub velocity: Vec3,
+}
+
+impl FollowLeaderRule {
+    pub fn new(radius: f32, factor: f32, velocity: Vec3) -> Self {
+        Self {
+            radius,
+            behind: BOID_SIZE * 3.,
+            factor,
+            velocity,
+        }
+    }
+}
+
+pub(crate) fn leader_script_system(
+    time: Res<Time>,
+    mut leaders: Query<(&BoidMovement, &LeaderScript, &mut SteeringVelocities), With<Leader>>,
+) {
+    for (movement, script, mut velocities) in &mut leaders {
+        let direction = (script.0)(time.elapsed_secs()).normalize_or_zero();
+        velocities.0.push(direction.extend(0.) * movement.speed);
+    }
+}
+
+pub(crate) fn keyboard_steering_system(
+    key_input: Option<Res<ButtonInput<KeyCode>>>,
+    mut leaders: Query<
+        (
+            &Transform,
+            &BoidMovement,
+            &KeyboardSteering,
+            &mut SteeringVelocities,
+        ),
+        With<Leader>,
+    >,
+) {
+    let Some(key_input) = key_input else {
+        return;
+    };
+
+    for (transform, movement, keys, mut velocities) in &mut leaders {
+        let heading = (transform.rotation * Vec3::Y).xy();
+        let turn = match (key_input.pressed(keys.left), key_input.pressed(keys.right)) {
+            (true, false) => FRAC_PI_2,
+            (false, true) => -FRAC_PI_2,
+            _ => 0.,
+        };
+
+        velocities
+            .0
+            .push((Vec2::from_angle(turn).rotate(heading) * movement.speed).extend(0.));
+    }
+}
+
+// Reynolds' leader following: arrive at a point behind the nearest leader in range,
+// or get out of its way first when in front of it.
+// Leaders are seen across the seam of a wrapped arena, at their nearest copy.
+pub(crate) fn follow_leader_system(
+    index: Res<NeighborIndex>,
+    leaders: Query<&Transform, With<Leader>>,
+    mut boids: Query<
+        (
+            &Transform,
+            &BoidMovement,
+            Option<&Velocity>,
+            &mut FollowLeaderRule,
+        ),
+        Without<Leader>,
+    >,
+) {
+    boids
+        .par_iter_mut()
+        .for_each(|(transform, movement, velocity, mut follow)| {
+            let position = transform.translation.xy();
+
+            let Some((leader_position, leader_heading)) = leaders
+                .iter()
+                .map(|leader| {
+                    let offset = index.offset(transform.translation, leader.translation);
+                    (position + offset.xy(), (leader.rotation * Vec3::Y).xy())
+                })
+                .filter(|(leader, _)| leader.distance(position) <= follow.radius)
+                .min_by(|(a, _), (b, _)| {
+                    a.distance_squared(position)
+                        .total_cmp(&b.distance_squared(position))
+                })
+            else {
+                follow.velocity = Vec3::ZERO;
+                return;
+            };
+
+            let offset = position - leader_position;
+            let ahead = offset.dot(leader_heading);
+            let aside = leader_heading.perp_dot(offset);
+
+            let steering = if ahead > 0. && aside.abs() < LEADER_PATH_WIDTH {
+                // step off the leader's path, to the side the boid is already on
+                let side = if aside < 0. {
+                    -leader_heading.perp()
+                } else {
+                    leader_heading.perp()
+                };
+                side * movement.speed
+            } else {
+                seek(
+                    position,
+                    current_velocity(transform, movement, velocity),
+                    leader_position - leader_heading * follow.behind,
+                    movement.speed,
+                    follow.behind,
+                )
+            };
+
+            follow.velocity = steering.extend(0.) * follow.factor;
+        });
+}
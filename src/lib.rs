@@ -1,26 +1,111 @@
+use std::f32::consts::TAU;
+
 use bevy::{
     color::LinearRgba,
     ecs::system::{Query, Res},
+    gizmos::config::GizmoConfigStore,
     prelude::*,
     time::Time,
     transform::components::Transform,
-    window::WindowResized,
+    utils::{HashSet, Parallel},
+    window::{PrimaryWindow, WindowResized},
+};
+
+mod boundary;
+mod cursor;
+mod distribution;
+mod flow;
+mod goals;
+mod kinematics;
+mod leader;
+mod neighbors;
+mod obstacle;
+mod predator;
+mod space;
+mod spawn;
+mod species;
+mod steering;
+pub mod testing;
+mod wander;
+
+pub use boundary::BoundaryMode;
+use boundary::{boids_boundary_system, boundary_walls_visibility_system, camera_follow_system};
+pub use cursor::{Cursor, CursorPlugin};
+pub use distribution::{BoidDistributions, Distribution};
+pub use flow::FlowField;
+use goals::{attraction_system, goals_gizmo_system, path_follow_system, seek_system};
+pub use goals::{AttractionRule, Attractor, Falloff, PathFollowRule, Repeller, SeekRule};
+use kinematics::{boids_physics_system, constant_speed_model, physical_model};
+pub use kinematics::{Acceleration, KinematicLimits, MovementModel, Velocity};
+use leader::{follow_leader_system, keyboard_steering_system, leader_script_system};
+pub use leader::{FollowLeaderRule, KeyboardSteering, Leader, LeaderScript};
+use neighbors::neighbor_index_system;
+pub use neighbors::{Neighbor, NeighborIndex};
+use obstacle::{obstacle_avoidance_system, obstacle_mesh_system};
+pub use obstacle::{Obstacle, ObstacleAvoidanceRule, ObstacleShape};
+use predator::{
+    capture_system, despawn_captured_system, flee_system, predator_hunt_system,
+    predator_mesh_system,
 };
+pub use predator::{BoidCaptured, FleeRule, HuntStrategy, Predator, PredatorBundle};
+use space::{arena_gizmo_system, orbit_camera_system, planar_space, volume_setup, volume_space};
+pub use space::{Arena, BoidSpace, OrbitCamera};
+use spawn::spawn_boids_event_system;
+pub use spawn::{BoidBundle, BoidSpawn, RuleParams, SpawnBoids, BOID_SIZE};
+pub use species::{species_color, Species, SpeciesInteraction, SpeciesInteractions};
+use steering::clear_steering_velocities_system;
+pub use steering::{SteeringRule, SteeringRuleAppExt, SteeringVelocities};
+use wander::wander_system;
+pub use wander::{perlin_noise, WanderRule};
+
+/// Order of the simulation within `FixedUpdate`.
+/// Custom rules belong in [`BoidSet::Rules`].
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BoidSet {
+    Index,
+    Rules,
+    Movement,
+}
+
+// members of the flock, predators move like boids but are not part of it
+pub(crate) type FlockFilter = (With<BoidMovement>, Without<Predator>);
+
+fn configure_boid_sets(app: &mut App) {
+    app.configure_sets(
+        FixedUpdate,
+        (BoidSet::Index, BoidSet::Rules, BoidSet::Movement).chain(),
+    );
+}
 
 // MOVEMENT
 pub struct MovementPlugin;
 
 impl Plugin for MovementPlugin {
     fn build(&self, app: &mut App) {
+        configure_boid_sets(app);
+        app.init_resource::<BlendMode>();
+        app.init_resource::<BoidSpace>();
+        app.init_resource::<BoundaryMode>();
+        app.init_resource::<MovementModel>();
+        app.init_resource::<WorldBounds>();
+        app.add_event::<SpawnBoids>();
+        app.add_systems(PreUpdate, world_bounds_window_system);
+        app.add_systems(Update, spawn_boids_event_system);
         app.add_systems(
             FixedUpdate,
             (
-                boids_rotation_system,
+                boids_rotation_system.run_if(constant_speed_model),
                 rule_velocity_comb_system,
-                boids_forward_movement_system,
-                boids_teleport_system,
+                boids_forward_movement_system.run_if(constant_speed_model),
+                boids_physics_system.run_if(physical_model),
+                boids_boundary_system,
             )
-                .chain(),
+                .chain()
+                .in_set(BoidSet::Movement),
+        );
+        app.add_systems(
+            FixedUpdate,
+            clear_steering_velocities_system.after(BoidSet::Movement),
         );
     }
 }
@@ -29,110 +114,217 @@ impl Plugin for MovementPlugin {
 pub struct BoidMovement {
     pub id: usize,
     pub speed: f32,
-    pub target_angle: f32,
+    // direction the boid turns towards, it keeps its heading while zero
+    pub target_direction: Vec3,
     pub rotation_speed: f32,
 }
 
 impl BoidMovement {
-    pub fn new(id: usize, speed: f32, target_angle: f32, rotation_speed: f32) -> Self {
+    pub fn new(id: usize, speed: f32, target_direction: Vec3, rotation_speed: f32) -> Self {
         Self {
             id,
             speed,
-            target_angle,
+            target_direction,
             rotation_speed,
         }
     }
 }
 
-// 2D Rotation Example helped a lot with this.
-// https://bevyengine.org/examples/2D%20Rendering/rotation/
-fn boids_rotation_system(time: Res<Time>, mut query: Query<(&mut Transform, &BoidMovement)>) {
-    for (mut transform, movement) in &mut query {
-        let curr_vel = (transform.rotation * Vec3::Y).xy();
-        let target_vel = Vec2::from_angle(movement.target_angle).normalize();
-        let target_vel_dot = curr_vel.normalize().dot(target_vel);
-
-        if (target_vel_dot - 1.).abs() < f32::EPSILON {
-            continue;
-        }
-
-        let right_vector = (transform.rotation * Vec3::X).xy();
-
-        let right_vector_dot = right_vector.dot(target_vel);
+/// `rotation` turned by at most `max_angle` so its forward (+Y) axis points closer to `target`.
+/// Turns about the boid's own Z axis when `target` is straight behind,
+/// so boids in the plane stay in it.
+pub fn turn_towards(rotation: Quat, target: Vec3, max_angle: f32) -> Quat {
+    let forward = rotation * Vec3::Y;
+    let Some(target) = target.try_normalize() else {
+        return rotation;
+    };
+
+    let angle = forward.angle_between(target);
+    if angle < f32::EPSILON {
+        return rotation;
+    }
 
-        let rotation_sign = -f32::copysign(1.0, right_vector_dot);
+    let axis = forward
+        .cross(target)
+        .try_normalize()
+        .unwrap_or(rotation * Vec3::Z);
+    (Quat::from_axis_angle(axis, angle.min(max_angle)) * rotation).normalize()
+}
 
-        let max_angle = target_vel_dot.clamp(-1.0, 1.0).acos();
+fn boids_rotation_system(time: Res<Time>, mut query: Query<(&mut Transform, &BoidMovement)>) {
+    for (mut transform, movement) in &mut query {
+        transform.rotation = turn_towards(
+            transform.rotation,
+            movement.target_direction,
+            movement.rotation_speed * time.delta_secs(),
+        );
+    }
+}
 
-        let rotation_angle =
-            rotation_sign * (movement.rotation_speed * time.delta_secs()).min(max_angle);
+/// How rule velocities are combined into a boid's target heading.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Default)]
+pub enum BlendMode {
+    /// Sum of the rule velocities, which are already scaled by each rule's `factor`.
+    Weighted,
+    /// Sum of the rule directions, ignoring their magnitude and `factor`.
+    #[default]
+    Normalized,
+    /// Reynolds' prioritized acceleration allocation.
+    /// Rules are added in priority order until their magnitudes use up `budget`;
+    /// obstacle avoidance comes first, then fleeing, separation, alignment, cohesion,
+    /// attraction, leader following, seeking, path following, wandering and custom rules.
+    Prioritized { budget: f32 },
+}
 
-        transform.rotate_z(rotation_angle);
+impl BlendMode {
+    pub fn blend(&self, velocities: impl IntoIterator<Item = Vec3>) -> Vec3 {
+        match *self {
+            BlendMode::Weighted => velocities.into_iter().sum(),
+            BlendMode::Normalized => velocities.into_iter().map(Vec3::normalize_or_zero).sum(),
+            BlendMode::Prioritized { budget } => {
+                let mut remaining = budget;
+                let mut velocity = Vec3::ZERO;
+
+                for rule_velocity in velocities {
+                    if remaining <= 0. {
+                        break;
+                    }
+
+                    let clamped = rule_velocity.clamp_length_max(remaining);
+                    remaining -= clamped.length();
+                    velocity += clamped;
+                }
+
+                velocity
+            }
+        }
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn rule_velocity_comb_system(
+    blend_mode: Res<BlendMode>,
+    boundary_mode: Res<BoundaryMode>,
+    bounds: Res<WorldBounds>,
+    space: Res<BoidSpace>,
     mut query: Query<(
+        &Transform,
         &mut BoidMovement,
+        &ObstacleAvoidanceRule,
+        &FleeRule,
         &SeparationRule,
         &AlignmentRule,
         &CohesionRule,
+        (&AttractionRule, &FollowLeaderRule, &WanderRule),
+        (Option<&SeekRule>, Option<&PathFollowRule>),
+        Option<&SteeringVelocities>,
+        Option<(&mut Acceleration, &KinematicLimits)>,
     )>,
 ) {
-    for (mut movement, separation, alignment, cohesion) in &mut query {
-        let velocities = [separation.velocity, alignment.velocity, cohesion.velocity];
-        let velocity: Vec2 = velocities.iter().map(|v| v.normalize()).sum();
+    let arena = space.arena(bounds.0);
+
+    for (
+        transform,
+        mut movement,
+        avoidance,
+        flee,
+        separation,
+        alignment,
+        cohesion,
+        (attraction, follow, wander),
+        (seek, path),
+        custom,
+        physics,
+    ) in &mut query
+    {
+        let velocities = [
+            avoidance.velocity,
+            flee.velocity,
+            separation.velocity,
+            alignment.velocity,
+            cohesion.velocity,
+            attraction.velocity,
+            follow.velocity,
+        ]
+        .into_iter()
+        .chain(seek.map(|seek| seek.velocity))
+        .chain(path.map(|path| path.velocity))
+        .chain([wander.velocity])
+        .chain(
+            custom
+                .into_iter()
+                .flat_map(|custom| custom.0.iter().copied()),
+        );
+        let velocity = space.flatten(boundary_mode.steer_away(
+            blend_mode.blend(velocities),
+            transform.translation,
+            arena,
+            movement.speed,
+        ));
 
-        if !velocity.is_nan() {
-            movement.target_angle = velocity.to_angle();
+        if let Some((mut acceleration, limits)) = physics {
+            acceleration.0 = velocity.clamp_length_max(limits.max_force);
+        }
+
+        // no rule is steering, keep the current heading
+        if let Some(direction) = velocity.try_normalize() {
+            movement.target_direction = direction;
         }
     }
 }
 
 fn boids_forward_movement_system(
     time: Res<Time>,
-    mut query: Query<(&mut Transform, &BoidMovement), With<BoidMovement>>,
+    flow: Option<Res<FlowField>>,
+    mut query: Query<(&mut Transform, &BoidMovement, Option<&mut Velocity>), With<BoidMovement>>,
 ) {
-    for (mut transform, movement) in &mut query {
+    for (mut transform, movement, velocity) in &mut query {
         let movement_direction = transform.rotation * Vec3::Y;
         let movement_distance = movement.speed * time.delta_secs();
         let translation_delta = movement_direction * movement_distance;
         transform.translation += translation_delta;
+
+        // drift carries the boid along without turning it
+        if let Some(flow) = &flow {
+            let drift = flow.sample(transform.translation.xy()) * time.delta_secs();
+            transform.translation += drift.extend(0.);
+        }
+
+        // keeps switching to the physical model seamless
+        if let Some(mut velocity) = velocity {
+            velocity.0 = movement_direction * movement.speed;
+        }
+    }
+}
+
+/// Area the boids live in, see [`BoundaryMode`] for what happens at its edges.
+/// Follows the primary window when there is one, so the simulation also runs headless.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct WorldBounds(pub Rect);
+
+impl Default for WorldBounds {
+    fn default() -> Self {
+        Self::from_size(INITIAL_WINDOW_SIZE)
+    }
+}
+
+impl WorldBounds {
+    pub fn from_size(size: Vec2) -> Self {
+        Self(Rect::from_center_size(Vec2::ZERO, size))
     }
 }
 
-fn boids_teleport_system(
-    mut query: Query<&mut Transform, With<BoidMovement>>,
-    window_query: Query<&Window>,
+fn world_bounds_window_system(
+    mut bounds: ResMut<WorldBounds>,
+    space: Res<BoidSpace>,
+    window_query: Query<&Window, (With<PrimaryWindow>, Changed<Window>)>,
 ) {
-    let window = window_query.single();
-    let left_bound: f32 = -(window.width() / 2.);
-    let right_bound: f32 = window.width() / 2.;
-    let bottom_bound: f32 = -(window.height() / 2.);
-    let top_bound: f32 = window.height() / 2.;
-
-    for mut transform in &mut query {
-        let center = transform.translation.xy();
-
-        match center.x {
-            x if x > right_bound => {
-                transform.translation.x = left_bound;
-            }
-            x if x < left_bound => {
-                transform.translation.x = right_bound;
-            }
-            _ => (),
-        }
+    if space.is_volume() {
+        return;
+    }
 
-        match center.y {
-            y if y > top_bound => {
-                transform.translation.y = bottom_bound;
-            }
-            y if y < bottom_bound => {
-                transform.translation.y = top_bound;
-            }
-            _ => (),
-        }
+    if let Ok(window) = window_query.get_single() {
+        bounds.set_if_neq(WorldBounds::from_size(window.size()));
     }
 }
 
@@ -141,259 +333,719 @@ pub struct RulesPlugin;
 
 impl Plugin for RulesPlugin {
     fn build(&self, app: &mut App) {
+        configure_boid_sets(app);
+        app.init_resource::<BoidSpace>();
+        app.init_resource::<NeighborIndex>();
+        app.init_resource::<SpeciesInteractions>();
+        app.add_systems(FixedUpdate, neighbor_index_system.in_set(BoidSet::Index));
         app.add_systems(
             FixedUpdate,
-            (separation_system, alignment_system, cohesion_system).chain(),
+            (
+                (
+                    perception_system,
+                    obstacle_avoidance_system,
+                    flee_system,
+                    predator_hunt_system,
+                    attraction_system,
+                    seek_system,
+                    path_follow_system,
+                    follow_leader_system,
+                    leader_script_system,
+                    keyboard_steering_system,
+                    wander_system,
+                ),
+                (rules_gizmo_system, goals_gizmo_system)
+                    .run_if(resource_exists::<GizmoConfigStore>),
+            )
+                .chain()
+                .in_set(BoidSet::Rules),
         );
+        app.add_event::<BoidCaptured>();
+        // catches boids where the index saw them, before anything moves
+        app.add_systems(
+            FixedUpdate,
+            (capture_system, despawn_captured_system)
+                .chain()
+                .in_set(BoidSet::Rules),
+        );
+        app.add_systems(Update, (obstacle_mesh_system, predator_mesh_system));
+    }
+}
+
+/// Part of the perception circle a rule sees, centered on the boid's heading.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FieldOfView {
+    // full angle of the view cone in radians, TAU sees all around
+    pub view_angle: f32,
+    // full angle of the cone hidden behind the boid in radians
+    pub blind_spot: f32,
+}
+
+impl Default for FieldOfView {
+    fn default() -> Self {
+        Self {
+            view_angle: TAU,
+            blind_spot: 0.,
+        }
+    }
+}
+
+impl FieldOfView {
+    pub fn new(view_angle: f32, blind_spot: f32) -> Self {
+        Self {
+            view_angle,
+            blind_spot,
+        }
+    }
+
+    /// Angle of the cone that is actually visible.
+    pub fn visible_angle(&self) -> f32 {
+        self.view_angle.min(TAU - self.blind_spot).clamp(0., TAU)
+    }
+
+    pub fn is_limited(&self) -> bool {
+        self.visible_angle() < TAU
+    }
+
+    /// Whether a neighbor at `offset` from a boid flying along `heading` is in view.
+    /// In 3D the view is a cone around `heading`.
+    pub fn contains(&self, heading: Vec3, offset: Vec3) -> bool {
+        if !self.is_limited() || offset == Vec3::ZERO {
+            return true;
+        }
+
+        heading.angle_between(offset) <= self.visible_angle() / 2.
+    }
+}
+
+/// How a rule picks the neighbors it reacts to, among those in its field of view.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum NeighborSelection {
+    /// Every boid within the rule radius.
+    #[default]
+    Metric,
+    /// The `k` nearest boids, however far away they are, all weighted the same.
+    Nearest { k: usize },
+    /// The `k` nearest boids within the rule radius.
+    NearestWithin { k: usize },
+}
+
+impl NeighborSelection {
+    /// Whether boids beyond the rule radius are ignored.
+    pub fn is_bounded(&self) -> bool {
+        !matches!(self, NeighborSelection::Nearest { .. })
+    }
+}
+
+/// Draws the part of a perception circle seen through `fov`.
+pub fn draw_vision_cone(
+    gizmos: &mut Gizmos,
+    center: Vec2,
+    heading: Vec2,
+    radius: f32,
+    fov: FieldOfView,
+    color: impl Into<Color> + Copy,
+) {
+    if !fov.is_limited() {
+        gizmos.circle_2d(center, radius, color);
+        return;
+    }
+
+    let half_angle = fov.visible_angle() / 2.;
+    // arcs start at the rotated +Y and run counter-clockwise
+    let rotation = Rot2::radians(Vec2::Y.angle_to(heading) - half_angle);
+    gizmos.arc_2d(
+        Isometry2d::new(center, rotation),
+        fov.visible_angle(),
+        radius,
+        color,
+    );
+
+    for side in [-half_angle, half_angle] {
+        let edge = Vec2::from_angle(side).rotate(heading.normalize()) * radius;
+        gizmos.line_2d(center, center + edge, color);
+    }
+}
+
+// The perception sphere seen through `fov` in a volume:
+// the whole sphere, or the rim of the view cone with a few lines out to it.
+fn draw_vision_cone_3d(
+    gizmos: &mut Gizmos,
+    center: Vec3,
+    heading: Vec3,
+    radius: f32,
+    fov: FieldOfView,
+    color: impl Into<Color> + Copy,
+) {
+    if !fov.is_limited() {
+        gizmos.sphere(Isometry3d::from_translation(center), radius, color);
+        return;
+    }
+
+    let half_angle = fov.visible_angle() / 2.;
+    let rotation = Quat::from_rotation_arc(Vec3::Z, heading.normalize());
+    let rim_center = center + rotation * Vec3::Z * radius * half_angle.cos();
+    let rim_radius = radius * half_angle.sin();
+    gizmos.circle(Isometry3d::new(rim_center, rotation), rim_radius, color);
+
+    for side in [Vec3::X, Vec3::NEG_X, Vec3::Y, Vec3::NEG_Y] {
+        gizmos.line(center, rim_center + rotation * side * rim_radius, color);
     }
 }
 
 #[derive(Component)]
 pub struct SeparationRule {
-    pub id: usize,
     pub radius: f32,
     // between 0.0 and 1.0
     // 0 means off
     pub factor: f32,
-    pub velocity: Vec2,
+    pub fov: FieldOfView,
+    pub selection: NeighborSelection,
+    pub velocity: Vec3,
 }
 
 impl SeparationRule {
-    pub fn new(id: usize, radius: f32, factor: f32, velocity: Vec2) -> Self {
+    pub fn new(radius: f32, factor: f32, velocity: Vec3) -> Self {
         Self {
-            id,
             radius,
             factor,
+            fov: FieldOfView::default(),
+            selection: NeighborSelection::default(),
             velocity,
         }
     }
+
+    pub fn with_fov(mut self, fov: FieldOfView) -> Self {
+        self.fov = fov;
+        self
+    }
+
+    pub fn with_selection(mut self, selection: NeighborSelection) -> Self {
+        self.selection = selection;
+        self
+    }
 }
 
 #[derive(Component)]
 pub struct AlignmentRule {
-    pub id: usize,
     pub radius: f32,
     // between 0.0 and 1.0
     // 0 means off
     pub factor: f32,
-    pub velocity: Vec2,
+    pub fov: FieldOfView,
+    pub selection: NeighborSelection,
+    pub velocity: Vec3,
 }
 
 impl AlignmentRule {
-    pub fn new(id: usize, radius: f32, factor: f32, velocity: Vec2) -> Self {
+    pub fn new(radius: f32, factor: f32, velocity: Vec3) -> Self {
         Self {
-            id,
             radius,
             factor,
+            fov: FieldOfView::default(),
+            selection: NeighborSelection::default(),
             velocity,
         }
     }
+
+    pub fn with_fov(mut self, fov: FieldOfView) -> Self {
+        self.fov = fov;
+        self
+    }
+
+    pub fn with_selection(mut self, selection: NeighborSelection) -> Self {
+        self.selection = selection;
+        self
+    }
 }
 
 #[derive(Component)]
 pub struct CohesionRule {
-    pub id: usize,
     pub radius: f32,
     // between 0.0 and 1.0
     // 0 means off
     pub factor: f32,
-    pub velocity: Vec2,
+    pub fov: FieldOfView,
+    pub selection: NeighborSelection,
+    pub velocity: Vec3,
 }
 
 impl CohesionRule {
-    pub fn new(id: usize, radius: f32, factor: f32, velocity: Vec2) -> Self {
+    pub fn new(radius: f32, factor: f32, velocity: Vec3) -> Self {
         Self {
-            id,
             radius,
             factor,
+            fov: FieldOfView::default(),
+            selection: NeighborSelection::default(),
             velocity,
         }
     }
-}
 
-fn separation_system(
-    mut gizmos: Gizmos,
-    mut query: Query<(&Transform, &mut SeparationRule, &BoidMovement)>,
-) {
-    let mut velocities: [Option<Vec2>; BOID_COUNT] = [Option::None; BOID_COUNT];
-    for (current_transform, current_separation, current_movement) in &query {
-        let current_center = current_transform.translation.xy();
-        let mut nearby_boid_count = 0_u8;
-        let mut velocity = Vec2::ZERO;
-
-        if current_movement.id == DEBUG_BOID_ID {
-            gizmos.circle_2d(current_center, current_separation.radius, LinearRgba::RED);
-            gizmos.arrow_2d(
-                current_center,
-                current_center + current_separation.velocity,
-                LinearRgba::RED,
-            );
-        }
-
-        for (transform, separation, _) in &query {
-            if separation.id == current_separation.id {
-                continue;
-            }
+    pub fn with_fov(mut self, fov: FieldOfView) -> Self {
+        self.fov = fov;
+        self
+    }
 
-            let center = transform.translation.xy();
-            let distance = current_center.distance(center);
-            if distance > current_separation.radius {
-                continue;
-            }
+    pub fn with_selection(mut self, selection: NeighborSelection) -> Self {
+        self.selection = selection;
+        self
+    }
+}
 
-            let init_velocity = current_center - center;
-            let weight = (current_separation.radius - distance) / current_separation.radius;
-            let weighted_velocity = init_velocity.normalize() * weight * current_movement.speed;
+/// Running sum of one rule's contributions over a boid's neighbors.
+/// Neighbors of a species the rule ignores, or weighs negatively, are not counted at all.
+#[derive(Default)]
+struct RuleAccumulator {
+    sum: Vec3,
+    weight: f32,
+    count: usize,
+}
 
-            velocity += weighted_velocity;
-            nearby_boid_count += 1;
+impl RuleAccumulator {
+    fn add(&mut self, value: Vec3, weight: f32) {
+        if weight.is_nan() || weight <= 0. {
+            return;
         }
 
-        if nearby_boid_count > 0 {
-            velocity /= nearby_boid_count as f32;
-            velocity *= current_separation.factor;
+        self.sum += value * weight;
+        self.weight += weight;
+        self.count += 1;
+    }
 
-            velocities[current_separation.id] = Some(velocity);
-        }
+    fn mean(&self) -> Option<Vec3> {
+        (self.count > 0).then(|| self.sum / self.count as f32)
     }
 
-    for (_, mut separation, _) in &mut query {
-        let vel = velocities[separation.id].unwrap_or(Vec2::ZERO);
-        separation.velocity = vel;
+    fn weighted_mean(&self) -> Option<Vec3> {
+        (self.weight > 0.).then(|| self.sum / self.weight)
+    }
+
+    fn mean_weight(&self) -> f32 {
+        self.weight / self.count.max(1) as f32
     }
 }
 
-fn alignment_system(
-    mut gizmos: Gizmos,
-    mut query: Query<(&Transform, &mut AlignmentRule, &BoidMovement)>,
-) {
-    let mut velocities: [Option<Vec2>; BOID_COUNT] = [Option::None; BOID_COUNT];
-    for (current_transform, current_alignment, current_movement) in &query {
-        let current_center = current_transform.translation.xy();
-        let mut nearby_boid_count = 0_u8;
-        let mut velocity = Vec2::ZERO;
-
-        if current_movement.id == DEBUG_BOID_ID {
-            gizmos.circle_2d(current_center, current_alignment.radius, LinearRgba::GREEN);
-            gizmos.arrow_2d(
-                current_center,
-                current_center + current_alignment.velocity,
-                LinearRgba::GREEN,
-            );
+/// A neighbor as seen from the boid perceiving it.
+#[derive(Clone, Copy)]
+struct Sighting {
+    neighbor: Neighbor,
+    offset: Vec3,
+    distance: f32,
+}
+
+impl Sighting {
+    fn new(boid: &Neighbor, neighbor: Neighbor) -> Self {
+        let offset = neighbor.position - boid.position;
+        Self {
+            neighbor,
+            offset,
+            distance: offset.length(),
         }
+    }
+}
 
-        for (transform, alignment, _) in &query {
-            // skip over current boid
-            if alignment.id == current_alignment.id {
-                continue;
-            }
+// Buffers one perception thread reuses from boid to boid, so a tick does not allocate.
+#[derive(Default)]
+struct PerceptionScratch {
+    candidates: Vec<Sighting>,
+    selected: Vec<Sighting>,
+    seen: HashSet<Entity>,
+}
 
-            // filter out out-of-reach boids
-            let center = transform.translation.xy();
-            let distance = current_center.distance(center);
-            if distance > current_alignment.radius {
-                continue;
+impl PerceptionScratch {
+    // Other boids within `radius` of `boid` into `candidates`,
+    // each one at its nearest wrapped copy.
+    fn sightings(&mut self, index: &NeighborIndex, boid: &Neighbor, radius: f32) {
+        self.candidates.clear();
+        index.for_each_within(boid.position, radius, |neighbor| {
+            if neighbor.entity != boid.entity {
+                self.candidates.push(Sighting::new(boid, neighbor));
             }
+        });
+
+        if index.may_repeat(radius) {
+            self.candidates
+                .sort_by(|a, b| a.distance.total_cmp(&b.distance));
+            self.seen.clear();
+            self.candidates
+                .retain(|sighting| self.seen.insert(sighting.neighbor.entity));
+        }
+    }
 
-            let init_velocity = (transform.rotation * Vec3::Y).xy();
-            let weight = (current_alignment.radius - distance) / current_alignment.radius;
-            let weighted_velocity = init_velocity.normalize() * weight * current_movement.speed;
-
-            velocity += weighted_velocity;
-            nearby_boid_count += 1;
-
-            if current_movement.id == DEBUG_BOID_ID {
-                gizmos.line_2d(current_center, center, LinearRgba::BLUE);
+    // A rule's neighbors, which `candidates` must all hold for bounded selections.
+    // Metric neighbors are filtered straight out of `candidates`,
+    // the others are picked into `selected` first.
+    fn select_neighbors(
+        &mut self,
+        index: &NeighborIndex,
+        boid: &Neighbor,
+        radius: f32,
+        fov: FieldOfView,
+        selection: NeighborSelection,
+    ) -> impl Iterator<Item = &Sighting> {
+        let heading = boid.heading;
+        let in_view = move |sighting: &&Sighting| {
+            sighting.distance <= radius && fov.contains(heading, sighting.offset)
+        };
+
+        self.selected.clear();
+        match selection {
+            NeighborSelection::Metric => {}
+            NeighborSelection::Nearest { k } => self.selected.extend(
+                index
+                    .nearest(boid.position, k, f32::INFINITY, |neighbor| {
+                        neighbor.entity != boid.entity
+                            && fov.contains(boid.heading, neighbor.position - boid.position)
+                    })
+                    .into_iter()
+                    .map(|neighbor| Sighting::new(boid, neighbor)),
+            ),
+            NeighborSelection::NearestWithin { k } => {
+                self.selected.extend(self.candidates.iter().filter(in_view));
+                self.selected
+                    .sort_by(|a, b| a.distance.total_cmp(&b.distance));
+                self.selected.truncate(k);
             }
         }
 
-        if nearby_boid_count > 0 {
-            velocity /= nearby_boid_count as f32;
-            velocity *= current_alignment.factor;
-
-            velocities[current_alignment.id] = Some(velocity);
-        }
+        let metric = selection == NeighborSelection::Metric;
+        let neighbors = if metric {
+            &self.candidates
+        } else {
+            &self.selected
+        };
+        neighbors
+            .iter()
+            .filter(move |sighting| !metric || in_view(sighting))
     }
+}
 
-    for (_, mut alignment, _) in &mut query {
-        let vel = velocities[alignment.id].unwrap_or(Vec2::ZERO);
-        alignment.velocity = vel;
+// Weight of a neighbor at `distance`, fading out towards the rule radius.
+// Topological neighbors count the same however far away they are.
+fn falloff(selection: NeighborSelection, radius: f32, distance: f32) -> f32 {
+    if selection.is_bounded() {
+        (radius - distance) / radius
+    } else {
+        1.
     }
 }
 
-fn cohesion_system(
-    mut gizmos: Gizmos,
-    mut query: Query<(&Transform, &mut CohesionRule, &BoidMovement)>,
+// Finds each boid's neighbors once, within the largest of its bounded rule radii,
+// and feeds separation, alignment and cohesion from the same pass.
+// Each neighbor counts as much as the species matrix says for its rule.
+#[allow(clippy::type_complexity)]
+fn perception_system(
+    index: Res<NeighborIndex>,
+    interactions: Res<SpeciesInteractions>,
+    scratch: Local<Parallel<PerceptionScratch>>,
+    mut query: Query<(
+        Entity,
+        &Transform,
+        &mut SeparationRule,
+        &mut AlignmentRule,
+        &mut CohesionRule,
+        &BoidMovement,
+        Option<&Species>,
+    )>,
 ) {
-    let mut velocities: [Option<Vec2>; BOID_COUNT] = [Option::None; BOID_COUNT];
-    for (current_transform, current_cohesion, current_movement) in &query {
-        let current_center = current_transform.translation.xy();
-        let mut nearby_boid_count = 0_u8;
-        let mut center_of_mass = current_center;
-        let mut boid_positions: Vec<Vec2> = vec![];
-
-        if current_movement.id == DEBUG_BOID_ID {
-            gizmos.circle_2d(current_center, current_cohesion.radius, LinearRgba::WHITE);
-            gizmos.arrow_2d(
-                current_center,
-                current_center + current_cohesion.velocity,
-                LinearRgba::WHITE,
+    query.par_iter_mut().for_each(
+        |(
+            current_entity,
+            transform,
+            mut separation,
+            mut alignment,
+            mut cohesion,
+            movement,
+            species,
+        )| {
+            let current_center = transform.translation;
+            let boid = Neighbor {
+                entity: current_entity,
+                position: current_center,
+                heading: transform.rotation * Vec3::Y,
+                species: species.copied().unwrap_or_default(),
+            };
+            let interaction =
+                |neighbor: &Neighbor| interactions.get(boid.species, neighbor.species);
+
+            let perception_radius = [
+                (separation.radius, separation.selection),
+                (alignment.radius, alignment.selection),
+                (cohesion.radius, cohesion.selection),
+            ]
+            .into_iter()
+            .filter(|(_, selection)| selection.is_bounded())
+            .map(|(radius, _)| radius)
+            .fold(0., f32::max);
+            let mut scratch = scratch.borrow_local_mut();
+            scratch.sightings(&index, &boid, perception_radius);
+
+            let mut separation_acc = RuleAccumulator::default();
+            let neighbors = scratch.select_neighbors(
+                &index,
+                &boid,
+                separation.radius,
+                separation.fov,
+                separation.selection,
             );
-        }
+            for sighting in neighbors {
+                let init_velocity = -sighting.offset;
+                let weight = falloff(separation.selection, separation.radius, sighting.distance);
+                // boids on the same spot cannot tell which way is away
+                separation_acc.add(
+                    init_velocity.normalize_or_zero() * weight * movement.speed,
+                    interaction(&sighting.neighbor).separation,
+                );
+            }
 
-        for (transform, cohesion, _) in &query {
-            if cohesion.id == current_cohesion.id {
-                continue;
+            let mut alignment_acc = RuleAccumulator::default();
+            let neighbors = scratch.select_neighbors(
+                &index,
+                &boid,
+                alignment.radius,
+                alignment.fov,
+                alignment.selection,
+            );
+            for sighting in neighbors {
+                let init_velocity = sighting.neighbor.heading;
+                let weight = falloff(alignment.selection, alignment.radius, sighting.distance);
+                alignment_acc.add(
+                    init_velocity.normalize_or_zero() * weight * movement.speed,
+                    interaction(&sighting.neighbor).alignment,
+                );
             }
 
-            let center = transform.translation.xy();
-            let distance = current_center.distance(center);
-            if distance > current_cohesion.radius {
-                continue;
+            let mut cohesion_acc = RuleAccumulator::default();
+            let neighbors = scratch.select_neighbors(
+                &index,
+                &boid,
+                cohesion.radius,
+                cohesion.fov,
+                cohesion.selection,
+            );
+            for sighting in neighbors {
+                cohesion_acc.add(
+                    sighting.neighbor.position,
+                    interaction(&sighting.neighbor).cohesion,
+                );
             }
 
-            center_of_mass += center;
-            nearby_boid_count += 1;
+            separation.velocity = separation_acc
+                .mean()
+                .map_or(Vec3::ZERO, |velocity| velocity * separation.factor);
+
+            alignment.velocity = alignment_acc
+                .mean()
+                .map_or(Vec3::ZERO, |velocity| velocity * alignment.factor);
+
+            let cohesion_strength = cohesion_acc.mean_weight();
+            cohesion.velocity = cohesion_acc
+                .weighted_mean()
+                .map_or(Vec3::ZERO, |center_of_mass| {
+                    let com_vector = center_of_mass - current_center;
+                    let weight = falloff(cohesion.selection, cohesion.radius, com_vector.length());
+                    com_vector.normalize_or_zero()
+                        * weight
+                        * cohesion_strength
+                        * movement.speed
+                        * cohesion.factor
+                });
+        },
+    );
+}
 
-            boid_positions.push(center);
+#[allow(clippy::type_complexity)]
+fn rules_gizmo_system(
+    mut gizmos: Gizmos,
+    index: Res<NeighborIndex>,
+    interactions: Res<SpeciesInteractions>,
+    space: Res<BoidSpace>,
+    query: Query<(
+        Entity,
+        &Transform,
+        &SeparationRule,
+        &AlignmentRule,
+        &CohesionRule,
+        &ObstacleAvoidanceRule,
+        &BoidMovement,
+        Option<&Species>,
+    )>,
+) {
+    for (entity, transform, separation, alignment, cohesion, avoidance, movement, species) in &query
+    {
+        if movement.id != DEBUG_BOID_ID {
+            continue;
         }
 
-        if nearby_boid_count > 0 {
-            center_of_mass -= current_center;
-            center_of_mass /= nearby_boid_count as f32;
-
-            let com_vector = center_of_mass - current_center;
-            let weight = (current_cohesion.radius - com_vector.length()) / current_cohesion.radius;
-            let weighted_velocity =
-                com_vector.normalize() * weight * current_movement.speed * current_cohesion.factor;
+        let center = transform.translation;
+        let heading = transform.rotation * Vec3::Y;
 
-            velocities[current_cohesion.id] = Some(weighted_velocity);
+        for (radius, fov, velocity, color) in [
+            (
+                separation.radius,
+                separation.fov,
+                separation.velocity,
+                LinearRgba::RED,
+            ),
+            (
+                alignment.radius,
+                alignment.fov,
+                alignment.velocity,
+                LinearRgba::GREEN,
+            ),
+            (
+                cohesion.radius,
+                cohesion.fov,
+                cohesion.velocity,
+                LinearRgba::WHITE,
+            ),
+        ] {
+            if space.is_volume() {
+                draw_vision_cone_3d(&mut gizmos, center, heading, radius, fov, color);
+            } else {
+                draw_vision_cone(&mut gizmos, center.xy(), heading.xy(), radius, fov, color);
+            }
+            gizmos.arrow(center, center + velocity, color);
         }
-    }
 
-    for (_, mut cohesion, _) in &mut query {
-        let vel = velocities[cohesion.id].unwrap_or(Vec2::ZERO);
-        cohesion.velocity = vel;
+        gizmos.line(
+            center,
+            center + heading * avoidance.look_ahead,
+            LinearRgba::rgb(1., 1., 0.),
+        );
+        gizmos.arrow(
+            center,
+            center + avoidance.velocity,
+            LinearRgba::rgb(1., 1., 0.),
+        );
+
+        let boid = Neighbor {
+            entity,
+            position: center,
+            heading,
+            species: species.copied().unwrap_or_default(),
+        };
+        let mut scratch = PerceptionScratch::default();
+        scratch.sightings(&index, &boid, alignment.radius);
+        let neighbors = scratch.select_neighbors(
+            &index,
+            &boid,
+            alignment.radius,
+            alignment.fov,
+            alignment.selection,
+        );
+        // only the neighbors the species matrix lets the boid align with
+        for sighting in neighbors {
+            if interactions
+                .get(boid.species, sighting.neighbor.species)
+                .alignment
+                > 0.
+            {
+                gizmos.line(center, sighting.neighbor.position, LinearRgba::BLUE);
+            }
+        }
     }
 }
 
 // STARTUP
 // global properties
 pub const INITIAL_WINDOW_SIZE: Vec2 = Vec2::new(2560_f32, 1800_f32);
-pub const BOID_COUNT: usize = 128;
-pub const DEBUG_BOID_ID: usize = BOID_COUNT + 1;
+pub const DEFAULT_BOID_COUNT: usize = 128;
+pub const DEFAULT_SPECIES_COUNT: usize = 3;
+// no boid is spawned with this id, set it to a valid one to debug a boid
+pub const DEBUG_BOID_ID: usize = usize::MAX;
 
 // Walls
 const WALL_THICKNESS: f32 = 10.0;
 const WALL_Z: f32 = 10.0;
-const WALL_COLOR: LinearRgba = LinearRgba::GREEN;
+pub(crate) const WALL_COLOR: LinearRgba = LinearRgba::GREEN;
 
 pub struct StartupPlugin;
 
 impl Plugin for StartupPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup);
-        app.add_systems(Update, window_walls_resize_system);
+        app.init_resource::<FlockConfig>();
+        app.init_resource::<SimRng>();
+        app.init_resource::<BoidSpace>();
+        app.add_systems(
+            Startup,
+            (
+                setup.run_if(planar_space),
+                volume_setup.run_if(volume_space),
+            ),
+        );
+        app.add_systems(
+            Update,
+            (
+                // the walls and the 2D camera only exist in the plane
+                (
+                    window_walls_resize_system,
+                    boundary_walls_visibility_system,
+                    camera_follow_system,
+                )
+                    .run_if(planar_space),
+                orbit_camera_system,
+                arena_gizmo_system.run_if(resource_exists::<GizmoConfigStore>),
+            ),
+        );
+    }
+}
+
+/// Flock parameters read once on startup.
+/// Insert it before adding `StartupPlugin` to override the defaults.
+#[derive(Resource, Debug, Clone)]
+pub struct FlockConfig {
+    pub boid_count: usize,
+    // boids are spread evenly over this many species, each with its own palette
+    pub species_count: usize,
+    // random when not set, the chosen seed is logged on startup
+    pub seed: Option<u64>,
+    pub params: BoidDistributions,
+}
+
+impl Default for FlockConfig {
+    fn default() -> Self {
+        Self {
+            boid_count: DEFAULT_BOID_COUNT,
+            species_count: DEFAULT_SPECIES_COUNT,
+            seed: None,
+            params: BoidDistributions::default(),
+        }
+    }
+}
+
+impl FlockConfig {
+    /// A boid of a random species, colored from its palette, with parameters drawn from `params`.
+    pub fn sample(&self, rng: &mut fastrand::Rng, position: Vec2, heading: f32) -> BoidSpawn {
+        let species = Species(rng.usize(..self.species_count.max(1)));
+        BoidSpawn {
+            species,
+            color: species_color(rng, species, self.species_count),
+            ..self.params.sample(rng, position, heading)
+        }
+    }
+}
+
+/// Source of all simulation randomness.
+/// Runs with the same seed spawn and steer the flock identically.
+#[derive(Resource, Debug, Clone, Deref, DerefMut)]
+pub struct SimRng(pub fastrand::Rng);
+
+impl SimRng {
+    pub fn new(seed: u64) -> Self {
+        Self(fastrand::Rng::with_seed(seed))
+    }
+}
+
+impl FromWorld for SimRng {
+    fn from_world(world: &mut World) -> Self {
+        let seed = world
+            .get_resource::<FlockConfig>()
+            .and_then(|config| config.seed)
+            .unwrap_or_else(|| fastrand::u64(..));
+        info!("simulation seed: {seed}");
+
+        Self::new(seed)
     }
 }
 
@@ -436,6 +1088,8 @@ fn setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    config: Res<FlockConfig>,
+    mut rng: ResMut<SimRng>,
 ) {
     commands.spawn(Camera2d);
 
@@ -457,37 +1111,19 @@ fn setup(
         ));
     }
 
-    let grids_vec = tile_window(BOID_COUNT as u32);
-    assert!(grids_vec.len() >= BOID_COUNT);
-
-    for (idx, grid) in grids_vec.iter().take(BOID_COUNT).enumerate() {
-        let direction_degrees = (fastrand::f32() * 360.0).to_radians();
-        let target_degrees = (fastrand::f32() * 360.0).to_radians();
-        let rand_color = make_random_pastel_color();
-
-        commands.spawn((
-            Mesh2d(meshes.add(RegularPolygon::new(20., 3))),
-            MeshMaterial2d(materials.add(ColorMaterial::from(rand_color))),
-            Transform::from_xyz(grid.x, grid.y, idx as f32)
-                .with_rotation(Quat::from_rotation_z(direction_degrees)),
-            SeparationRule::new(idx, 175., 1., Vec2::ZERO),
-            AlignmentRule::new(idx, 100., 1., Vec2::ZERO),
-            CohesionRule::new(idx, 200., 1., Vec2::ZERO),
-            BoidMovement::new(idx, 150., target_degrees, std::f32::consts::PI / 2.),
-        ));
-    }
-}
+    let grids_vec = tile_window(config.boid_count as u32);
+    assert!(grids_vec.len() >= config.boid_count);
 
-fn make_random_pastel_color() -> Color {
-    const LIGHT_BLUE_R: f32 = 173. / 255.;
-    const LIGHT_BLUE_G: f32 = 216. / 255.;
-    const LIGHT_BLUE_B: f32 = 230. / 255.;
+    let spawns = grids_vec
+        .iter()
+        .take(config.boid_count)
+        .map(|grid| {
+            let heading = (rng.f32() * 360.0).to_radians();
+            config.sample(&mut rng, grid.pos(), heading)
+        })
+        .collect();
 
-    Color::srgb(
-        (fastrand::f32() + LIGHT_BLUE_R) / 2.,
-        (fastrand::f32() + LIGHT_BLUE_G) / 2.,
-        (fastrand::f32() + LIGHT_BLUE_B) / 2.,
-    )
+    commands.queue(SpawnBoids(spawns));
 }
 
 /// get min square number for given boid count
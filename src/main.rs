@@ -1,10 +1,27 @@
-use bevy::{prelude::*, window::WindowResolution};
+use std::f32::consts::TAU;
 
-use boids_rs::{MovementPlugin, RulesPlugin, StartupPlugin, INITIAL_WINDOW_SIZE};
+use bevy::{prelude::*, ui::RelativeCursorPosition, utils::HashMap, window::WindowResolution};
+
+use boids_rs::{
+    species_color, AlignmentRule, Attractor, BoidBundle, BoidDistributions, BoidMovement,
+    BoidSpawn, BoundaryMode, CohesionRule, Cursor, CursorPlugin, Distribution, Falloff,
+    FlockConfig, FlowField, HuntStrategy, KeyboardSteering, Leader, MovementPlugin,
+    NeighborSelection, Obstacle, PredatorBundle, Repeller, RuleParams, RulesPlugin, SeparationRule,
+    SimRng, SpawnBoids, Species, SpeciesInteractions, StartupPlugin, WorldBounds, BOID_SIZE,
+    INITIAL_WINDOW_SIZE,
+};
 
 fn main() {
     App::new()
         .insert_resource(Time::<Fixed>::from_hz(60.))
+        .insert_resource(SpeciesInteractions::kin_only())
+        .insert_resource(FlockConfig {
+            params: BoidDistributions {
+                wander_strength: Distribution::uniform(0.2, 0.6),
+                ..default()
+            },
+            ..default()
+        })
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
                 resolution: WindowResolution::new(INITIAL_WINDOW_SIZE.x, INITIAL_WINDOW_SIZE.y),
@@ -17,6 +34,764 @@ fn main() {
         .add_plugins(StartupPlugin)
         .add_plugins(MovementPlugin)
         .add_plugins(RulesPlugin)
+        .add_plugins(CursorPlugin)
+        .init_resource::<TuningGroup>()
+        .init_resource::<SpeciesParams>()
+        .add_systems(
+            Startup,
+            (
+                spawn_obstacles,
+                spawn_predators,
+                spawn_leader,
+                spawn_tuning_panel,
+            ),
+        )
+        .add_systems(
+            Update,
+            (boundary_mode_system, flow_field_system, mouse_system),
+        )
+        .add_systems(
+            Update,
+            (
+                panel_toggle_system,
+                group_button_system,
+                slider_drag_system,
+                slider_sync_system,
+                slider_display_system,
+            )
+                .chain(),
+        )
         // .add_systems(Update, close_on_esc)
         .run();
 }
+
+fn spawn_obstacles(mut commands: Commands) {
+    commands.spawn((Obstacle::circle(80.), Transform::from_xyz(-600., 250., 5.)));
+    commands.spawn((
+        Obstacle::rectangle(Vec2::new(240., 80.)),
+        Transform::from_xyz(500., -300., 5.).with_rotation(Quat::from_rotation_z(0.4)),
+    ));
+    commands.spawn((
+        Obstacle::polygon([
+            Vec2::new(-90., -70.),
+            Vec2::new(90., -70.),
+            Vec2::new(0., 100.),
+        ]),
+        Transform::from_xyz(300., 400., 5.),
+    ));
+}
+
+fn spawn_predators(mut commands: Commands) {
+    commands.spawn(PredatorBundle::new(
+        Vec2::new(-1000., -700.),
+        0.,
+        HuntStrategy::Nearest,
+    ));
+    commands.spawn(PredatorBundle::new(
+        Vec2::new(1000., 700.),
+        std::f32::consts::PI,
+        HuntStrategy::MostIsolated,
+    ));
+}
+
+// outside the range of ids handed out to the flock
+const LEADER_ID: usize = usize::MAX - 2;
+
+// the arrow keys steer the leader
+fn spawn_leader(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let leader = BoidSpawn {
+        alignment: RuleParams::new(100., 0.),
+        cohesion: RuleParams::new(200., 0.),
+        ..BoidSpawn::new(Vec2::ZERO, 0.)
+    };
+
+    commands.spawn((
+        BoidBundle::new(LEADER_ID, &leader),
+        Leader,
+        KeyboardSteering::default(),
+        Mesh2d(meshes.add(RegularPolygon::new(BOID_SIZE * 1.5, 3))),
+        MeshMaterial2d(materials.add(ColorMaterial::from_color(Color::srgb(1., 0.8, 0.)))),
+    ));
+}
+
+// B cycles through the boundary modes
+fn boundary_mode_system(key_input: Res<ButtonInput<KeyCode>>, mut mode: ResMut<BoundaryMode>) {
+    if key_input.just_pressed(KeyCode::KeyB) {
+        *mode = match *mode {
+            BoundaryMode::Wrap => BoundaryMode::Bounce,
+            BoundaryMode::Bounce => BoundaryMode::SteerAway { margin: 150. },
+            BoundaryMode::SteerAway { .. } => BoundaryMode::Open,
+            BoundaryMode::Open => BoundaryMode::Wrap,
+        };
+        info!("boundary mode: {:?}", *mode);
+    }
+}
+
+// F cycles through calm air, wind, a vortex and shear over the world
+fn flow_field_system(
+    mut commands: Commands,
+    key_input: Res<ButtonInput<KeyCode>>,
+    bounds: Res<WorldBounds>,
+    flow: Option<Res<FlowField>>,
+    mut pattern: Local<usize>,
+) {
+    const FLOW_CELLS: usize = 32;
+    const FLOW_STRENGTH: f32 = 60.;
+
+    if !key_input.just_pressed(KeyCode::KeyF) {
+        return;
+    }
+
+    *pattern = if flow.is_some() {
+        (*pattern + 1) % 4
+    } else {
+        1
+    };
+    let area = bounds.0;
+    let field = match *pattern {
+        1 => Some(FlowField::uniform(
+            area,
+            Vec2::new(1., 0.5).normalize() * FLOW_STRENGTH,
+        )),
+        2 => Some(FlowField::vortex(area, FLOW_CELLS, FLOW_STRENGTH)),
+        3 => Some(FlowField::shear(area, FLOW_CELLS, FLOW_STRENGTH)),
+        _ => None,
+    };
+
+    match field {
+        Some(field) => commands.insert_resource(field),
+        None => commands.remove_resource::<FlowField>(),
+    }
+    info!(
+        "flow field: {}",
+        ["calm", "wind", "vortex", "shear"][*pattern]
+    );
+}
+
+// boids spawned by a shift-click
+const CLICK_SPAWN_COUNT: usize = 8;
+// radius of the disc they are scattered over
+const CLICK_SPAWN_RADIUS: f32 = BOID_SIZE * 3.;
+
+// attractor or repeller following the cursor while a mouse button is held
+#[derive(Component)]
+struct CursorField;
+
+// left-drag attracts, right-drag repels, shift-click spawns boids
+#[allow(clippy::too_many_arguments)]
+fn mouse_system(
+    mut commands: Commands,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    key_input: Res<ButtonInput<KeyCode>>,
+    cursor: Res<Cursor>,
+    config: Res<FlockConfig>,
+    species_params: Res<SpeciesParams>,
+    mut rng: ResMut<SimRng>,
+    mut field_query: Query<(Entity, &mut Transform), With<CursorField>>,
+    ui_query: Query<&Interaction>,
+) {
+    // clicks on the tuning panel are not meant for the flock
+    if ui_query
+        .iter()
+        .any(|interaction| *interaction != Interaction::None)
+    {
+        for (entity, _) in &field_query {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+
+    let modifier = key_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    if modifier && mouse_input.just_pressed(MouseButton::Left) {
+        // a click spawns a small flock of one species
+        let species = Species(rng.usize(..config.species_count.max(1)));
+        let params = species_params.0.get(&species).unwrap_or(&config.params);
+        let spawns = (0..CLICK_SPAWN_COUNT)
+            .map(|_| {
+                let heading = rng.f32() * TAU;
+                // uniform over the disc, boids on one spot have no way to separate
+                let offset =
+                    Vec2::from_angle(rng.f32() * TAU) * CLICK_SPAWN_RADIUS * rng.f32().sqrt();
+                BoidSpawn {
+                    species,
+                    color: species_color(&mut rng, species, config.species_count),
+                    ..params.sample(&mut rng, cursor.pos + offset, heading)
+                }
+            })
+            .collect();
+        commands.queue(SpawnBoids(spawns));
+    }
+
+    let attract = !modifier && mouse_input.pressed(MouseButton::Left);
+    let repel = !modifier && !attract && mouse_input.pressed(MouseButton::Right);
+    let pressed = mouse_input.any_just_pressed([MouseButton::Left, MouseButton::Right]);
+    let released = mouse_input.any_just_released([MouseButton::Left, MouseButton::Right]);
+
+    // a button change can switch between attracting and repelling
+    if pressed || released || !(attract || repel) {
+        for (entity, _) in &field_query {
+            commands.entity(entity).despawn();
+        }
+    } else {
+        for (_, mut transform) in &mut field_query {
+            transform.translation = cursor.pos.extend(0.);
+        }
+        return;
+    }
+
+    let transform = Transform::from_translation(cursor.pos.extend(0.));
+    if attract {
+        commands.spawn((
+            Attractor::new(1.5, 600., Falloff::Linear),
+            transform,
+            CursorField,
+        ));
+    } else if repel {
+        commands.spawn((
+            Repeller::new(2., 400., Falloff::Linear),
+            transform,
+            CursorField,
+        ));
+    }
+}
+
+// boids the tuning panel edits, every species when `None`
+#[derive(Resource, Default)]
+struct TuningGroup(Option<Species>);
+
+// spawn parameters tuned for a single species, the others spawn from `FlockConfig`
+#[derive(Resource, Default)]
+struct SpeciesParams(HashMap<Species, BoidDistributions>);
+
+// root of the tuning panel, Tab shows or hides it
+#[derive(Component)]
+struct TuningPanel;
+
+// picks the group the sliders apply to
+#[derive(Component)]
+struct GroupButton(Option<Species>);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Rule {
+    Separation,
+    Alignment,
+    Cohesion,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RuleField {
+    Radius,
+    Factor,
+    ViewAngle,
+    BlindSpot,
+    // 0 means every neighbor within the radius
+    Neighbors,
+}
+
+// one tunable field of a boid
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Param {
+    Rule(Rule, RuleField),
+    Speed,
+    RotationSpeed,
+}
+
+impl Param {
+    const ALL: [Param; 17] = [
+        Param::Rule(Rule::Separation, RuleField::Radius),
+        Param::Rule(Rule::Separation, RuleField::Factor),
+        Param::Rule(Rule::Separation, RuleField::ViewAngle),
+        Param::Rule(Rule::Separation, RuleField::BlindSpot),
+        Param::Rule(Rule::Separation, RuleField::Neighbors),
+        Param::Rule(Rule::Alignment, RuleField::Radius),
+        Param::Rule(Rule::Alignment, RuleField::Factor),
+        Param::Rule(Rule::Alignment, RuleField::ViewAngle),
+        Param::Rule(Rule::Alignment, RuleField::BlindSpot),
+        Param::Rule(Rule::Alignment, RuleField::Neighbors),
+        Param::Rule(Rule::Cohesion, RuleField::Radius),
+        Param::Rule(Rule::Cohesion, RuleField::Factor),
+        Param::Rule(Rule::Cohesion, RuleField::ViewAngle),
+        Param::Rule(Rule::Cohesion, RuleField::BlindSpot),
+        Param::Rule(Rule::Cohesion, RuleField::Neighbors),
+        Param::Speed,
+        Param::RotationSpeed,
+    ];
+
+    fn section(&self) -> &'static str {
+        match self {
+            Param::Rule(Rule::Separation, _) => "Separation",
+            Param::Rule(Rule::Alignment, _) => "Alignment",
+            Param::Rule(Rule::Cohesion, _) => "Cohesion",
+            Param::Speed | Param::RotationSpeed => "Movement",
+        }
+    }
+
+    fn range(&self) -> (f32, f32) {
+        match self {
+            Param::Rule(_, RuleField::Radius) => (0., 400.),
+            Param::Rule(_, RuleField::Factor) => (0., 1.),
+            Param::Rule(_, RuleField::ViewAngle | RuleField::BlindSpot) => (0., TAU),
+            Param::Rule(_, RuleField::Neighbors) => (0., 20.),
+            Param::Speed => (0., 400.),
+            Param::RotationSpeed => (0., TAU),
+        }
+    }
+
+    fn label(&self, value: f32) -> String {
+        match self {
+            Param::Rule(_, RuleField::Radius) => format!("radius: {value:.0}"),
+            Param::Rule(_, RuleField::Factor) => format!("factor: {value:.2}"),
+            Param::Rule(_, RuleField::ViewAngle) => {
+                format!("view angle: {:.0}°", value.to_degrees())
+            }
+            Param::Rule(_, RuleField::BlindSpot) => {
+                format!("blind spot: {:.0}°", value.to_degrees())
+            }
+            Param::Rule(_, RuleField::Neighbors) if value < 1. => "neighbors: all".into(),
+            Param::Rule(_, RuleField::Neighbors) => format!("neighbors: {value:.0} nearest"),
+            Param::Speed => format!("speed: {value:.0}"),
+            Param::RotationSpeed => format!("rotation speed: {:.0}°/s", value.to_degrees()),
+        }
+    }
+
+    fn get(
+        &self,
+        separation: &SeparationRule,
+        alignment: &AlignmentRule,
+        cohesion: &CohesionRule,
+        movement: &BoidMovement,
+    ) -> f32 {
+        let rule = |rule: Rule| match rule {
+            Rule::Separation => RuleParams {
+                radius: separation.radius,
+                factor: separation.factor,
+                fov: separation.fov,
+                selection: separation.selection,
+            },
+            Rule::Alignment => RuleParams {
+                radius: alignment.radius,
+                factor: alignment.factor,
+                fov: alignment.fov,
+                selection: alignment.selection,
+            },
+            Rule::Cohesion => RuleParams {
+                radius: cohesion.radius,
+                factor: cohesion.factor,
+                fov: cohesion.fov,
+                selection: cohesion.selection,
+            },
+        };
+
+        match *self {
+            Param::Rule(kind, field) => {
+                let params = rule(kind);
+                match field {
+                    RuleField::Radius => params.radius,
+                    RuleField::Factor => params.factor,
+                    RuleField::ViewAngle => params.fov.view_angle,
+                    RuleField::BlindSpot => params.fov.blind_spot,
+                    RuleField::Neighbors => match params.selection {
+                        NeighborSelection::Metric => 0.,
+                        NeighborSelection::Nearest { k }
+                        | NeighborSelection::NearestWithin { k } => k as f32,
+                    },
+                }
+            }
+            Param::Speed => movement.speed,
+            Param::RotationSpeed => movement.rotation_speed,
+        }
+    }
+
+    fn set(
+        &self,
+        value: f32,
+        separation: &mut SeparationRule,
+        alignment: &mut AlignmentRule,
+        cohesion: &mut CohesionRule,
+        movement: &mut BoidMovement,
+    ) {
+        let (kind, field) = match *self {
+            Param::Rule(kind, field) => (kind, field),
+            Param::Speed => {
+                movement.speed = value;
+                return;
+            }
+            Param::RotationSpeed => {
+                movement.rotation_speed = value;
+                return;
+            }
+        };
+
+        let (radius, factor, fov, selection) = match kind {
+            Rule::Separation => (
+                &mut separation.radius,
+                &mut separation.factor,
+                &mut separation.fov,
+                &mut separation.selection,
+            ),
+            Rule::Alignment => (
+                &mut alignment.radius,
+                &mut alignment.factor,
+                &mut alignment.fov,
+                &mut alignment.selection,
+            ),
+            Rule::Cohesion => (
+                &mut cohesion.radius,
+                &mut cohesion.factor,
+                &mut cohesion.fov,
+                &mut cohesion.selection,
+            ),
+        };
+
+        match field {
+            RuleField::Radius => *radius = value,
+            RuleField::Factor => *factor = value,
+            RuleField::ViewAngle => fov.view_angle = value,
+            RuleField::BlindSpot => fov.blind_spot = value,
+            RuleField::Neighbors => *selection = with_neighbors(*selection, value),
+        }
+    }
+
+    // the same change for boids spawned from `params` later on
+    fn set_spawn(&self, value: f32, params: &mut BoidDistributions) {
+        let (kind, field) = match *self {
+            Param::Rule(kind, field) => (kind, field),
+            Param::Speed => {
+                params.speed = Distribution::Fixed(value);
+                return;
+            }
+            Param::RotationSpeed => {
+                params.rotation_speed = Distribution::Fixed(value);
+                return;
+            }
+        };
+
+        let (radius, factor, rule) = match kind {
+            Rule::Separation => (
+                &mut params.separation_radius,
+                &mut params.separation_factor,
+                &mut params.template.separation,
+            ),
+            Rule::Alignment => (
+                &mut params.alignment_radius,
+                &mut params.alignment_factor,
+                &mut params.template.alignment,
+            ),
+            Rule::Cohesion => (
+                &mut params.cohesion_radius,
+                &mut params.cohesion_factor,
+                &mut params.template.cohesion,
+            ),
+        };
+
+        match field {
+            RuleField::Radius => *radius = Distribution::Fixed(value),
+            RuleField::Factor => *factor = Distribution::Fixed(value),
+            RuleField::ViewAngle => rule.fov.view_angle = value,
+            RuleField::BlindSpot => rule.fov.blind_spot = value,
+            RuleField::Neighbors => rule.selection = with_neighbors(rule.selection, value),
+        }
+    }
+}
+
+// `selection` limited to the `value` nearest neighbors, all of them in the radius for 0
+fn with_neighbors(selection: NeighborSelection, value: f32) -> NeighborSelection {
+    let k = value.round() as usize;
+    match selection {
+        _ if k == 0 => NeighborSelection::Metric,
+        // keep a selection that reaches beyond the radius
+        NeighborSelection::Nearest { .. } => NeighborSelection::Nearest { k },
+        _ => NeighborSelection::NearestWithin { k },
+    }
+}
+
+// track of one slider, dragging along it sets `param` on the selected group
+#[derive(Component)]
+struct Slider {
+    param: Param,
+    value: f32,
+    label: Entity,
+    fill: Entity,
+}
+
+const PANEL_COLOR: Color = Color::srgba(0.1, 0.1, 0.1, 0.85);
+const TRACK_COLOR: Color = Color::srgb(0.25, 0.25, 0.25);
+const FILL_COLOR: Color = Color::srgb(0.5, 0.7, 0.9);
+const BUTTON_COLOR: Color = Color::srgb(0.25, 0.25, 0.25);
+const SELECTED_COLOR: Color = Color::srgb(0.35, 0.5, 0.7);
+
+fn spawn_tuning_panel(mut commands: Commands, config: Res<FlockConfig>) {
+    let text_font = TextFont {
+        font_size: 14.,
+        ..default()
+    };
+
+    let panel = commands
+        .spawn((
+            TuningPanel,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.),
+                right: Val::Px(10.),
+                width: Val::Px(260.),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(8.)),
+                row_gap: Val::Px(3.),
+                ..default()
+            },
+            BackgroundColor(PANEL_COLOR),
+            Interaction::default(),
+        ))
+        .id();
+
+    let groups = std::iter::once(None).chain((0..config.species_count).map(|i| Some(Species(i))));
+    let buttons = commands
+        .spawn(Node {
+            flex_wrap: FlexWrap::Wrap,
+            column_gap: Val::Px(4.),
+            row_gap: Val::Px(4.),
+            margin: UiRect::bottom(Val::Px(4.)),
+            ..default()
+        })
+        .with_children(|row| {
+            for group in groups {
+                let name = match group {
+                    None => "All".to_string(),
+                    Some(Species(i)) => format!("Species {}", i + 1),
+                };
+                row.spawn((
+                    Button,
+                    GroupButton(group),
+                    Node {
+                        padding: UiRect::axes(Val::Px(6.), Val::Px(2.)),
+                        ..default()
+                    },
+                    BackgroundColor(if group.is_none() {
+                        SELECTED_COLOR
+                    } else {
+                        BUTTON_COLOR
+                    }),
+                ))
+                .with_child((Text::new(name), text_font.clone()));
+            }
+        })
+        .id();
+    commands.entity(panel).add_child(buttons);
+
+    let mut section = "";
+    for param in Param::ALL {
+        if param.section() != section {
+            section = param.section();
+            let title = commands
+                .spawn((
+                    Text::new(section),
+                    TextFont {
+                        font_size: 16.,
+                        ..default()
+                    },
+                    Node {
+                        margin: UiRect::top(Val::Px(4.)),
+                        ..default()
+                    },
+                ))
+                .id();
+            commands.entity(panel).add_child(title);
+        }
+
+        let label = commands.spawn((Text::default(), text_font.clone())).id();
+        let fill = commands
+            .spawn((
+                Node {
+                    width: Val::Percent(0.),
+                    height: Val::Percent(100.),
+                    ..default()
+                },
+                BackgroundColor(FILL_COLOR),
+            ))
+            .id();
+        let track = commands
+            .spawn((
+                Slider {
+                    param,
+                    value: 0.,
+                    label,
+                    fill,
+                },
+                Button,
+                RelativeCursorPosition::default(),
+                Node {
+                    width: Val::Percent(100.),
+                    height: Val::Px(10.),
+                    ..default()
+                },
+                BackgroundColor(TRACK_COLOR),
+            ))
+            .add_child(fill)
+            .id();
+        commands.entity(panel).add_children(&[label, track]);
+    }
+}
+
+fn panel_toggle_system(
+    key_input: Res<ButtonInput<KeyCode>>,
+    mut panel_query: Query<&mut Visibility, With<TuningPanel>>,
+) {
+    if !key_input.just_pressed(KeyCode::Tab) {
+        return;
+    }
+
+    for mut visibility in &mut panel_query {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+fn group_button_system(
+    mut group: ResMut<TuningGroup>,
+    mut button_query: Query<(&Interaction, &GroupButton, &mut BackgroundColor)>,
+) {
+    let pressed = button_query
+        .iter()
+        .find(|(interaction, ..)| **interaction == Interaction::Pressed)
+        .map(|(_, button, _)| button.0);
+    let Some(selected) = pressed else {
+        return;
+    };
+    if group.0 == selected {
+        return;
+    }
+
+    group.0 = selected;
+    for (_, button, mut color) in &mut button_query {
+        color.0 = if button.0 == selected {
+            SELECTED_COLOR
+        } else {
+            BUTTON_COLOR
+        };
+    }
+}
+
+// dragging a slider applies its value to every boid of the group but the leader
+#[allow(clippy::type_complexity)]
+fn slider_drag_system(
+    group: Res<TuningGroup>,
+    mut config: ResMut<FlockConfig>,
+    mut species_params: ResMut<SpeciesParams>,
+    mut slider_query: Query<(&Interaction, &RelativeCursorPosition, &mut Slider)>,
+    mut boid_query: Query<
+        (
+            Option<&Species>,
+            &mut SeparationRule,
+            &mut AlignmentRule,
+            &mut CohesionRule,
+            &mut BoidMovement,
+        ),
+        Without<Leader>,
+    >,
+) {
+    for (interaction, cursor, mut slider) in &mut slider_query {
+        let Some(position) = cursor.normalized else {
+            continue;
+        };
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let (min, max) = slider.param.range();
+        let mut value = min + position.x.clamp(0., 1.) * (max - min);
+        if matches!(slider.param, Param::Rule(_, RuleField::Neighbors)) {
+            value = value.round();
+        }
+        slider.value = value;
+        for (species, mut separation, mut alignment, mut cohesion, mut movement) in &mut boid_query
+        {
+            if group
+                .0
+                .is_some_and(|group| species.copied().unwrap_or_default() != group)
+            {
+                continue;
+            }
+            slider.param.set(
+                value,
+                &mut separation,
+                &mut alignment,
+                &mut cohesion,
+                &mut movement,
+            );
+        }
+
+        // shift-click spawns pick up the tuned values
+        match group.0 {
+            None => {
+                slider.param.set_spawn(value, &mut config.params);
+                for params in species_params.0.values_mut() {
+                    slider.param.set_spawn(value, params);
+                }
+            }
+            Some(species) => {
+                let params = species_params.0.entry(species).or_insert(config.params);
+                slider.param.set_spawn(value, params);
+            }
+        }
+    }
+}
+
+// picking another group shows the values of its first boid
+#[allow(clippy::type_complexity)]
+fn slider_sync_system(
+    group: Res<TuningGroup>,
+    mut slider_query: Query<&mut Slider>,
+    boid_query: Query<
+        (
+            Option<&Species>,
+            &SeparationRule,
+            &AlignmentRule,
+            &CohesionRule,
+            &BoidMovement,
+        ),
+        Without<Leader>,
+    >,
+    mut synced: Local<bool>,
+) {
+    if *synced && !group.is_changed() {
+        return;
+    }
+
+    let first = boid_query.iter().find(|(species, ..)| {
+        group
+            .0
+            .is_none_or(|group| species.copied().unwrap_or_default() == group)
+    });
+    let Some((_, separation, alignment, cohesion, movement)) = first else {
+        return;
+    };
+
+    *synced = true;
+    for mut slider in &mut slider_query {
+        slider.value = slider.param.get(separation, alignment, cohesion, movement);
+    }
+}
+
+fn slider_display_system(
+    slider_query: Query<&Slider, Changed<Slider>>,
+    mut text_query: Query<&mut Text>,
+    mut node_query: Query<&mut Node>,
+) {
+    for slider in &slider_query {
+        let (min, max) = slider.param.range();
+        let share = ((slider.value - min) / (max - min)).clamp(0., 1.);
+        if let Ok(mut node) = node_query.get_mut(slider.fill) {
+            node.width = Val::Percent(share * 100.);
+        }
+        if let Ok(mut text) = text_query.get_mut(slider.label) {
+            text.0 = slider.param.label(slider.value);
+        }
+    }
+}
@@ -0,0 +1,301 @@
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+use crate::{
+    AlignmentRule, Arena, BoidSpace, BoundaryMode, CohesionRule, FlockFilter, SeparationRule,
+    Species, WorldBounds,
+};
+
+/// A boid as seen by its neighbors, captured when the index is rebuilt.
+#[derive(Debug, Clone, Copy)]
+pub struct Neighbor {
+    pub entity: Entity,
+    pub position: Vec3,
+    pub heading: Vec3,
+    pub species: Species,
+}
+
+/// Spatial hash over boid positions.
+/// Rebuilt once per `FixedUpdate` before the rules run, so rule systems
+/// only visit the grid cells around a boid instead of the whole flock.
+#[derive(Resource, Debug)]
+pub struct NeighborIndex {
+    cell_size: f32,
+    cells: HashMap<IVec3, Vec<Neighbor>>,
+    len: usize,
+    wrap: Option<Arena>,
+    // smallest box holding every inserted boid
+    extent: Option<Arena>,
+}
+
+impl Default for NeighborIndex {
+    fn default() -> Self {
+        Self::new(NeighborIndex::MIN_CELL_SIZE)
+    }
+}
+
+impl NeighborIndex {
+    // keeps the grid from degenerating when every rule radius is zero
+    const MIN_CELL_SIZE: f32 = 1.0;
+
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size: cell_size.max(Self::MIN_CELL_SIZE),
+            cells: HashMap::default(),
+            len: 0,
+            wrap: None,
+            extent: None,
+        }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes every boid and resizes the cells.
+    /// Cells work best when they are as large as the largest query radius.
+    pub fn clear(&mut self, cell_size: f32) {
+        self.cell_size = cell_size.max(Self::MIN_CELL_SIZE);
+        self.cells.clear();
+        self.len = 0;
+        self.extent = None;
+    }
+
+    /// Makes queries see across the sides of `arena`, as boids do when they wrap.
+    /// Sides closer than twice the query radius can report a neighbor more than once.
+    pub fn set_wrap(&mut self, arena: Option<Arena>) {
+        self.wrap = arena;
+    }
+
+    /// Whether a query of `radius` can report the same boid more than once,
+    /// reaching it across both sides of a wrapped arena.
+    pub fn may_repeat(&self, radius: f32) -> bool {
+        self.wrap
+            .is_some_and(|arena| 2. * radius >= arena.size().min_element())
+    }
+
+    /// Shortest offset from `from` to `to`, across the sides of a wrapped arena.
+    pub fn offset(&self, from: Vec3, to: Vec3) -> Vec3 {
+        let offset = to - from;
+        let Some(arena) = self.wrap else {
+            return offset;
+        };
+
+        // an unbounded side, like Z in the plane, is never crossed
+        let size = arena.size();
+        let wrapped = offset - (offset / size).round() * size;
+        Vec3::select(size.is_finite_mask(), wrapped, offset)
+    }
+
+    pub fn insert(&mut self, neighbor: Neighbor) {
+        let cell = self.cell(neighbor.position);
+        self.cells.entry(cell).or_default().push(neighbor);
+        self.len += 1;
+
+        let point = neighbor.position;
+        self.extent = Some(self.extent.map_or(
+            Arena {
+                min: point,
+                max: point,
+            },
+            |extent| Arena {
+                min: extent.min.min(point),
+                max: extent.max.max(point),
+            },
+        ));
+    }
+
+    /// Boids within `radius` of `center`, the boid at `center` included.
+    /// With wrapping on, neighbors across an edge are reported at their position
+    /// as seen from `center`, so distances and directions are toroidal.
+    pub fn query(&self, center: Vec3, radius: f32) -> impl Iterator<Item = Neighbor> + '_ {
+        let (shifts, len) = self.wrap_shifts(center, radius);
+
+        shifts.into_iter().take(len).flat_map(move |shift| {
+            self.query_cells(center - shift, radius)
+                .map(move |neighbor| Neighbor {
+                    position: neighbor.position + shift,
+                    ..*neighbor
+                })
+        })
+    }
+
+    /// Calls `visit` with the boids [`query`](Self::query) returns, in the same order.
+    /// Cheaper in hot loops, where the nested iterators of `query` do not optimize well.
+    pub fn for_each_within(&self, center: Vec3, radius: f32, mut visit: impl FnMut(Neighbor)) {
+        let (shifts, len) = self.wrap_shifts(center, radius);
+        let Some(extent) = self.extent else {
+            return;
+        };
+        let radius_squared = radius * radius;
+
+        for &shift in &shifts[..len] {
+            let local = center - shift;
+            let min = self.cell((local - radius).max(extent.min));
+            let max = self.cell((local + radius).min(extent.max));
+            for z in min.z..=max.z {
+                for y in min.y..=max.y {
+                    for x in min.x..=max.x {
+                        let Some(cell) = self.cells.get(&IVec3::new(x, y, z)) else {
+                            continue;
+                        };
+                        for neighbor in cell {
+                            if neighbor.position.distance_squared(local) <= radius_squared {
+                                visit(Neighbor {
+                                    position: neighbor.position + shift,
+                                    ..*neighbor
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// The `k` boids nearest to `center` that pass `filter`, closest first,
+    /// none of them further away than `max_radius`.
+    /// Searches outwards from `center` until enough boids are found.
+    pub fn nearest(
+        &self,
+        center: Vec3,
+        k: usize,
+        max_radius: f32,
+        filter: impl Fn(&Neighbor) -> bool,
+    ) -> Vec<Neighbor> {
+        let Some(extent) = self.extent.filter(|_| k > 0) else {
+            return Vec::new();
+        };
+
+        // far enough to see every boid, wrapped distances are never longer than half the arena
+        let mut corner = (extent.min - center).abs().max((extent.max - center).abs());
+        if let Some(arena) = self.wrap {
+            corner = corner.min(arena.size() / 2.);
+        }
+        let limit = max_radius.min(corner.length());
+
+        let mut radius = self.cell_size.min(limit);
+        loop {
+            let mut found: Vec<Neighbor> = self
+                .query(center, radius)
+                .filter(|neighbor| filter(neighbor))
+                .collect();
+
+            if found.len() >= k || radius >= limit {
+                found.sort_by(|a, b| {
+                    a.position
+                        .distance_squared(center)
+                        .total_cmp(&b.position.distance_squared(center))
+                });
+                // a search wider than the arena sees a boid once per wrapped copy
+                let mut seen = HashSet::new();
+                found.retain(|neighbor| seen.insert(neighbor.entity));
+                found.truncate(k);
+                return found;
+            }
+
+            radius = (radius * 2.).min(limit);
+        }
+    }
+
+    fn query_cells(&self, center: Vec3, radius: f32) -> impl Iterator<Item = &Neighbor> {
+        // only visit cells that can hold a boid, a planar flock fills a single layer
+        let (min, max) = match self.extent {
+            Some(extent) => (
+                self.cell((center - radius).max(extent.min)),
+                self.cell((center + radius).min(extent.max)),
+            ),
+            None => (IVec3::ONE, IVec3::ZERO),
+        };
+        let radius_squared = radius * radius;
+
+        (min.z..=max.z)
+            .flat_map(move |z| (min.y..=max.y).map(move |y| (y, z)))
+            .flat_map(move |(y, z)| (min.x..=max.x).map(move |x| IVec3::new(x, y, z)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .filter(move |neighbor| neighbor.position.distance_squared(center) <= radius_squared)
+    }
+
+    // Offsets of the copies of the world a query sphere reaches into.
+    // An unbounded side, like Z in the plane, is never crossed.
+    fn wrap_shifts(&self, center: Vec3, radius: f32) -> ([Vec3; 27], usize) {
+        let mut shifts = [Vec3::ZERO; 27];
+        let mut len = 1;
+
+        let Some(arena) = self.wrap else {
+            return (shifts, len);
+        };
+
+        let size = arena.size();
+        let axis_shifts = |axis: usize| {
+            [
+                Some(0.),
+                (center[axis] + radius > arena.max[axis]).then_some(size[axis]),
+                (center[axis] - radius < arena.min[axis]).then_some(-size[axis]),
+            ]
+        };
+
+        for x in axis_shifts(0).into_iter().flatten() {
+            for y in axis_shifts(1).into_iter().flatten() {
+                for z in axis_shifts(2).into_iter().flatten() {
+                    if x != 0. || y != 0. || z != 0. {
+                        shifts[len] = Vec3::new(x, y, z);
+                        len += 1;
+                    }
+                }
+            }
+        }
+
+        (shifts, len)
+    }
+
+    fn cell(&self, position: Vec3) -> IVec3 {
+        (position / self.cell_size).floor().as_ivec3()
+    }
+}
+
+pub(crate) fn neighbor_index_system(
+    mut index: ResMut<NeighborIndex>,
+    boundary_mode: Option<Res<BoundaryMode>>,
+    bounds: Option<Res<WorldBounds>>,
+    space: Option<Res<BoidSpace>>,
+    boids: Query<(Entity, &Transform, Option<&Species>), FlockFilter>,
+    rules: Query<(&SeparationRule, &AlignmentRule, &CohesionRule)>,
+) {
+    let max_radius = rules
+        .iter()
+        .map(|(separation, alignment, cohesion)| {
+            separation.radius.max(alignment.radius).max(cohesion.radius)
+        })
+        .fold(0., f32::max);
+
+    index.clear(max_radius);
+
+    let wrapping = boundary_mode.is_some_and(|mode| *mode == BoundaryMode::Wrap);
+    let space = space.map_or(BoidSpace::default(), |space| *space);
+    index.set_wrap(
+        bounds
+            .filter(|_| wrapping)
+            .map(|bounds| space.arena(bounds.0)),
+    );
+
+    for (entity, transform, species) in &boids {
+        index.insert(Neighbor {
+            entity,
+            position: transform.translation,
+            heading: transform.rotation * Vec3::Y,
+            species: species.copied().unwrap_or_default(),
+        });
+    }
+}
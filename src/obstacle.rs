@@ -0,0 +1,304 @@
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, PrimitiveTopology},
+        render_asset::RenderAssetUsages,
+    },
+};
+
+use crate::{BoidMovement, BoidSpace, BOID_SIZE, WALL_COLOR};
+
+// how close a boid may pass by an obstacle
+const CLEARANCE: f32 = BOID_SIZE;
+
+/// Shape of an [`Obstacle`], around the origin of its `Transform`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ObstacleShape {
+    Circle(Circle),
+    Rectangle(Rectangle),
+    /// Convex polygon with its vertices in counter-clockwise order.
+    Polygon(Vec<Vec2>),
+}
+
+impl ObstacleShape {
+    /// Point of the shape closest to `point`, both relative to the shape's origin.
+    /// Points inside the shape are their own closest point.
+    /// A polygon with fewer than 3 vertices is empty and has no closest point.
+    pub fn closest_point(&self, point: Vec2) -> Option<Vec2> {
+        match self {
+            ObstacleShape::Circle(circle) => Some(circle.closest_point(point)),
+            ObstacleShape::Rectangle(rectangle) => Some(rectangle.closest_point(point)),
+            ObstacleShape::Polygon(vertices) if vertices.len() < 3 => None,
+            ObstacleShape::Polygon(vertices) => {
+                let edges = || vertices.iter().zip(vertices.iter().cycle().skip(1));
+                let inside = edges().all(|(a, b)| (*b - *a).perp_dot(point - *a) >= 0.);
+                if inside {
+                    return Some(point);
+                }
+
+                edges()
+                    .map(|(a, b)| closest_on_segment(*a, *b, point))
+                    .min_by(|a, b| {
+                        a.distance_squared(point)
+                            .total_cmp(&b.distance_squared(point))
+                    })
+            }
+        }
+    }
+
+    /// Distance from the origin to the furthest point of the shape.
+    pub fn bounding_radius(&self) -> f32 {
+        match self {
+            ObstacleShape::Circle(circle) => circle.radius,
+            ObstacleShape::Rectangle(rectangle) => rectangle.half_size.length(),
+            ObstacleShape::Polygon(vertices) => vertices
+                .iter()
+                .map(|vertex| vertex.length())
+                .fold(0., f32::max),
+        }
+    }
+
+    pub fn mesh(&self) -> Mesh {
+        match self {
+            ObstacleShape::Circle(circle) => circle.mesh().build(),
+            ObstacleShape::Rectangle(rectangle) => rectangle.mesh().build(),
+            ObstacleShape::Polygon(vertices) => {
+                let positions: Vec<[f32; 3]> = vertices
+                    .iter()
+                    .map(|vertex| [vertex.x, vertex.y, 0.])
+                    .collect();
+                let indices = (2..vertices.len() as u32)
+                    .flat_map(|i| [0, i - 1, i])
+                    .collect();
+
+                Mesh::new(
+                    PrimitiveTopology::TriangleList,
+                    RenderAssetUsages::default(),
+                )
+                .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+                .with_inserted_indices(Indices::U32(indices))
+            }
+        }
+    }
+
+    /// The shape extruded `depth` along Z, centered on the origin.
+    pub fn column_mesh(&self, depth: f32) -> Mesh {
+        match self {
+            ObstacleShape::Circle(circle) => Extrusion::new(*circle, depth).mesh().build(),
+            ObstacleShape::Rectangle(rectangle) => Extrusion::new(*rectangle, depth).mesh().build(),
+            ObstacleShape::Polygon(vertices) => prism_mesh(vertices, depth),
+        }
+    }
+}
+
+// A convex polygon extruded along Z, with flat shaded caps and sides.
+fn prism_mesh(vertices: &[Vec2], depth: f32) -> Mesh {
+    let half_depth = depth / 2.;
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+
+    for (z, normal) in [(half_depth, Vec3::Z), (-half_depth, Vec3::NEG_Z)] {
+        let start = positions.len() as u32;
+        positions.extend(vertices.iter().map(|vertex| vertex.extend(z).to_array()));
+        normals.extend(vertices.iter().map(|_| normal.to_array()));
+        for i in 2..vertices.len() as u32 {
+            // the bottom cap faces down, so it winds the other way
+            if normal.z > 0. {
+                indices.extend([start, start + i - 1, start + i]);
+            } else {
+                indices.extend([start, start + i, start + i - 1]);
+            }
+        }
+    }
+
+    // counter-clockwise vertices have the outside on the right of each edge
+    for (a, b) in vertices.iter().zip(vertices.iter().cycle().skip(1)) {
+        let edge = *b - *a;
+        let normal = Vec2::new(edge.y, -edge.x).normalize_or_zero().extend(0.);
+        let start = positions.len() as u32;
+        positions.extend([
+            a.extend(-half_depth).to_array(),
+            b.extend(-half_depth).to_array(),
+            b.extend(half_depth).to_array(),
+            a.extend(half_depth).to_array(),
+        ]);
+        normals.extend([normal.to_array(); 4]);
+        indices.extend([start, start + 1, start + 2, start, start + 2, start + 3]);
+    }
+
+    let uvs = vec![[0., 0.]; positions.len()];
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+pub(crate) fn closest_on_segment(a: Vec2, b: Vec2, point: Vec2) -> Vec2 {
+    let edge = b - a;
+    let t = (point - a).dot(edge) / edge.length_squared().max(f32::EPSILON);
+    a + edge * t.clamp(0., 1.)
+}
+
+/// Something in the boids' way, placed by its `Transform`.
+/// Gets a mesh like the walls when the app can render one.
+/// In a [`BoidSpace::Volume`] it is a column through the whole depth of the box,
+/// which boids steer around sideways.
+#[derive(Component, Debug, Clone)]
+#[require(Transform)]
+pub struct Obstacle {
+    pub shape: ObstacleShape,
+}
+
+impl Obstacle {
+    pub fn circle(radius: f32) -> Self {
+        Self {
+            shape: ObstacleShape::Circle(Circle::new(radius)),
+        }
+    }
+
+    pub fn rectangle(size: Vec2) -> Self {
+        Self {
+            shape: ObstacleShape::Rectangle(Rectangle::from_size(size)),
+        }
+    }
+
+    /// A convex polygon, `vertices` in counter-clockwise order.
+    pub fn polygon(vertices: impl Into<Vec<Vec2>>) -> Self {
+        let vertices = vertices.into();
+        assert!(vertices.len() >= 3, "a polygon needs at least 3 vertices");
+
+        Self {
+            shape: ObstacleShape::Polygon(vertices),
+        }
+    }
+
+    /// Point of the obstacle closest to `point`, in world space.
+    pub fn closest_point(&self, transform: &Transform, point: Vec2) -> Option<Vec2> {
+        let (angle, _, _) = transform.rotation.to_euler(EulerRot::ZYX);
+        let isometry = Isometry2d::new(transform.translation.xy(), Rot2::radians(angle));
+
+        self.shape
+            .closest_point(isometry.inverse_transform_point(point))
+            .map(|closest| isometry.transform_point(closest))
+    }
+}
+
+#[derive(Component)]
+pub struct ObstacleAvoidanceRule {
+    // how far ahead along its heading the boid looks for obstacles
+    pub look_ahead: f32,
+    // between 0.0 and 1.0
+    // 0 means off
+    pub factor: f32,
+    pub velocity: Vec3,
+}
+
+impl ObstacleAvoidanceRule {
+    pub fn new(look_ahead: f32, factor: f32, velocity: Vec3) -> Self {
+        Self {
+            look_ahead,
+            factor,
+            velocity,
+        }
+    }
+}
+
+// Walks a probe out along each boid's heading and steers sideways, away from the
+// first obstacle it gets too close to; the sooner the hit, the stronger the turn.
+pub(crate) fn obstacle_avoidance_system(
+    obstacles: Query<(&Obstacle, &Transform)>,
+    mut boids: Query<(&Transform, &BoidMovement, &mut ObstacleAvoidanceRule), Without<Obstacle>>,
+) {
+    boids
+        .par_iter_mut()
+        .for_each(|(transform, movement, mut avoidance)| {
+            avoidance.velocity = Vec3::ZERO;
+            if avoidance.factor == 0. || avoidance.look_ahead <= 0. {
+                return;
+            }
+
+            // obstacles are columns along Z, only the horizontal part of the flight can hit them
+            let position = transform.translation.xy();
+            let forward = (transform.rotation * Vec3::Y).xy();
+            let Some(heading) = forward.try_normalize() else {
+                return;
+            };
+            let steps = (avoidance.look_ahead / (CLEARANCE / 2.)).ceil() as usize;
+
+            let mut nearest_hit: Option<(f32, Vec2, Vec2, Vec2)> = None;
+            for (obstacle, obstacle_transform) in &obstacles {
+                let center = obstacle_transform.translation.xy();
+                let reach = avoidance.look_ahead + obstacle.shape.bounding_radius() + CLEARANCE;
+                if position.distance_squared(center) > reach * reach {
+                    continue;
+                }
+
+                let hit = (0..=steps).find_map(|step| {
+                    let ahead = avoidance.look_ahead * step as f32 / steps as f32;
+                    let probe = position + forward * ahead;
+                    let surface = obstacle.closest_point(obstacle_transform, probe)?;
+                    (probe.distance(surface) < CLEARANCE).then_some((ahead, probe, surface, center))
+                });
+
+                if let Some(hit) =
+                    hit.filter(|hit| nearest_hit.is_none_or(|nearest| hit.0 < nearest.0))
+                {
+                    nearest_hit = Some(hit);
+                }
+            }
+
+            let Some((ahead, probe, surface, center)) = nearest_hit else {
+                return;
+            };
+
+            // a probe inside the obstacle is pushed out from its center
+            let away = (probe - surface)
+                .try_normalize()
+                .unwrap_or_else(|| (probe - center).normalize_or(-heading));
+            let side = (away - heading * away.dot(heading))
+                .try_normalize()
+                .unwrap_or_else(|| {
+                    // head-on, pass on the side the center is not on
+                    if heading.perp_dot(center - position) > 0. {
+                        -heading.perp()
+                    } else {
+                        heading.perp()
+                    }
+                });
+
+            let urgency = 1. - ahead / avoidance.look_ahead;
+            avoidance.velocity = (side * urgency * movement.speed * avoidance.factor).extend(0.);
+        });
+}
+
+pub(crate) fn obstacle_mesh_system(
+    mut commands: Commands,
+    space: Res<BoidSpace>,
+    meshes: Option<ResMut<Assets<Mesh>>>,
+    mut materials: Option<ResMut<Assets<ColorMaterial>>>,
+    mut materials_3d: Option<ResMut<Assets<StandardMaterial>>>,
+    query: Query<(Entity, &Obstacle), Changed<Obstacle>>,
+) {
+    let Some(mut meshes) = meshes else {
+        return;
+    };
+
+    for (entity, obstacle) in &query {
+        match (*space, &mut materials_3d, &mut materials) {
+            (BoidSpace::Volume { depth }, Some(materials), _) => commands.entity(entity).insert((
+                Mesh3d(meshes.add(obstacle.shape.column_mesh(depth))),
+                MeshMaterial3d(materials.add(StandardMaterial::from_color(WALL_COLOR))),
+            )),
+            (BoidSpace::Planar, _, Some(materials)) => commands.entity(entity).insert((
+                Mesh2d(meshes.add(obstacle.shape.mesh())),
+                MeshMaterial2d(materials.add(ColorMaterial::from_color(WALL_COLOR))),
+            )),
+            _ => return,
+        };
+    }
+}
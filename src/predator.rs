@@ -0,0 +1,264 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+use crate::{
+    Acceleration, BoidMovement, BoidSpace, KinematicLimits, NeighborIndex, Velocity, BOID_SIZE,
+};
+
+// predators are not boids, they all share an id no boid gets
+const PREDATOR_ID: usize = usize::MAX - 1;
+const PREDATOR_COLOR: LinearRgba = LinearRgba::RED;
+
+/// Which boid a [`Predator`] goes after.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum HuntStrategy {
+    #[default]
+    Nearest,
+    /// The boid furthest away from its own nearest neighbor, strays are easier prey.
+    MostIsolated,
+}
+
+/// An agent hunting boids.
+/// Moves like a boid, but is not one of the flock: boids flee it instead of flocking with it.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Predator {
+    pub strategy: HuntStrategy,
+    // boids further away than this are not hunted
+    pub sight: f32,
+    // boids closer than this are caught
+    pub capture_radius: f32,
+    pub target: Option<Entity>,
+}
+
+impl Predator {
+    pub fn new(strategy: HuntStrategy) -> Self {
+        Self {
+            strategy,
+            sight: 600.,
+            capture_radius: BOID_SIZE,
+            target: None,
+        }
+    }
+}
+
+/// Components of a predator, without any rendering.
+#[derive(Bundle)]
+pub struct PredatorBundle {
+    pub transform: Transform,
+    pub movement: BoidMovement,
+    pub velocity: Velocity,
+    pub acceleration: Acceleration,
+    pub limits: KinematicLimits,
+    pub predator: Predator,
+}
+
+impl PredatorBundle {
+    /// A predator slightly faster than the boids, but slower to turn.
+    pub fn new(position: Vec2, heading: f32, strategy: HuntStrategy) -> Self {
+        let speed = 180.;
+        let direction = Vec2::from_angle(heading).extend(0.);
+
+        Self {
+            transform: Transform::from_translation(position.extend(0.))
+                .with_rotation(Quat::from_rotation_z(heading - FRAC_PI_2)),
+            movement: BoidMovement::new(PREDATOR_ID, speed, direction, PI / 3.),
+            velocity: Velocity(direction * speed),
+            acceleration: Acceleration::default(),
+            limits: KinematicLimits::new(150., 50., 280.),
+            predator: Predator::new(strategy),
+        }
+    }
+}
+
+/// Boids closer than `radius` to a predator steer away from it.
+#[derive(Component)]
+pub struct FleeRule {
+    pub radius: f32,
+    // between 0.0 and 1.0
+    // 0 means off
+    pub factor: f32,
+    pub velocity: Vec3,
+}
+
+impl FleeRule {
+    pub fn new(radius: f32, factor: f32, velocity: Vec3) -> Self {
+        Self {
+            radius,
+            factor,
+            velocity,
+        }
+    }
+}
+
+/// Sent when a predator catches a boid, which is despawned right after.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoidCaptured {
+    pub predator: Entity,
+    pub prey: Entity,
+}
+
+// Looks for boids around each predator, so boids see predators across the wrap seam.
+pub(crate) fn flee_system(
+    index: Res<NeighborIndex>,
+    predators: Query<&Transform, With<Predator>>,
+    mut boids: Query<(&BoidMovement, &mut FleeRule), Without<Predator>>,
+    mut threats: Local<HashMap<Entity, (Vec3, usize)>>,
+) {
+    threats.clear();
+    let max_radius = boids.iter().map(|(_, flee)| flee.radius).fold(0., f32::max);
+
+    let mut nearest_copies = HashMap::<Entity, Vec3>::new();
+    for predator in &predators {
+        let position = predator.translation;
+
+        // the boids as seen from the predator, a wide radius can see a boid twice across the seam
+        nearest_copies.clear();
+        for boid in index.query(position, max_radius) {
+            let away = boid.position - position;
+            nearest_copies
+                .entry(boid.entity)
+                .and_modify(|nearest| {
+                    if away.length_squared() < nearest.length_squared() {
+                        *nearest = away;
+                    }
+                })
+                .or_insert(away);
+        }
+
+        for (&entity, &away) in &nearest_copies {
+            let Ok((movement, flee)) = boids.get(entity) else {
+                continue;
+            };
+
+            let distance = away.length();
+            if distance > flee.radius {
+                continue;
+            }
+
+            let weight = (flee.radius - distance) / flee.radius;
+            let threat = threats.entry(entity).or_default();
+            threat.0 += away.normalize_or_zero() * weight * movement.speed;
+            threat.1 += 1;
+        }
+    }
+
+    for (_, mut flee) in &mut boids {
+        flee.velocity = Vec3::ZERO;
+    }
+    for (entity, (sum, count)) in threats.iter() {
+        if let Ok((_, mut flee)) = boids.get_mut(*entity) {
+            flee.velocity = *sum / *count as f32 * flee.factor;
+        }
+    }
+}
+
+// Picks a target for each predator and turns it towards it.
+#[allow(clippy::type_complexity)]
+pub(crate) fn predator_hunt_system(
+    index: Res<NeighborIndex>,
+    mut predators: Query<(
+        &Transform,
+        &mut BoidMovement,
+        &mut Predator,
+        Option<(&mut Acceleration, &KinematicLimits)>,
+    )>,
+) {
+    for (transform, mut movement, mut predator, physics) in &mut predators {
+        let position = transform.translation;
+
+        let prey = match predator.strategy {
+            HuntStrategy::Nearest => index
+                .nearest(position, 1, predator.sight, |_| true)
+                .into_iter()
+                .next(),
+            HuntStrategy::MostIsolated => index
+                .query(position, predator.sight)
+                .map(|boid| {
+                    let isolation = index
+                        .nearest(boid.position, 1, f32::INFINITY, |other| {
+                            other.entity != boid.entity
+                        })
+                        .first()
+                        .map_or(f32::INFINITY, |other| {
+                            other.position.distance_squared(boid.position)
+                        });
+                    (boid, isolation)
+                })
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(boid, _)| boid),
+        };
+
+        predator.target = prey.map(|boid| boid.entity);
+
+        let Some(direction) = prey.and_then(|boid| (boid.position - position).try_normalize())
+        else {
+            continue;
+        };
+
+        movement.target_direction = direction;
+        if let Some((mut acceleration, limits)) = physics {
+            acceleration.0 = direction * limits.max_force;
+        }
+    }
+}
+
+pub(crate) fn capture_system(
+    index: Res<NeighborIndex>,
+    mut captures: EventWriter<BoidCaptured>,
+    predators: Query<(Entity, &Transform, &Predator)>,
+) {
+    let mut caught = HashSet::new();
+
+    for (entity, transform, predator) in &predators {
+        for prey in index.query(transform.translation, predator.capture_radius) {
+            if caught.insert(prey.entity) {
+                captures.send(BoidCaptured {
+                    predator: entity,
+                    prey: prey.entity,
+                });
+            }
+        }
+    }
+}
+
+pub(crate) fn despawn_captured_system(
+    mut commands: Commands,
+    mut captures: EventReader<BoidCaptured>,
+) {
+    for capture in captures.read() {
+        if let Some(mut prey) = commands.get_entity(capture.prey) {
+            prey.despawn();
+        }
+    }
+}
+
+pub(crate) fn predator_mesh_system(
+    mut commands: Commands,
+    space: Res<BoidSpace>,
+    meshes: Option<ResMut<Assets<Mesh>>>,
+    mut materials: Option<ResMut<Assets<ColorMaterial>>>,
+    mut materials_3d: Option<ResMut<Assets<StandardMaterial>>>,
+    query: Query<Entity, Added<Predator>>,
+) {
+    let Some(mut meshes) = meshes else {
+        return;
+    };
+
+    for entity in &query {
+        match (space.is_volume(), &mut materials_3d, &mut materials) {
+            (true, Some(materials), _) => commands.entity(entity).insert((
+                Mesh3d(meshes.add(Cone::new(BOID_SIZE * 0.75, BOID_SIZE * 2.25))),
+                MeshMaterial3d(materials.add(StandardMaterial::from_color(PREDATOR_COLOR))),
+            )),
+            (false, _, Some(materials)) => commands.entity(entity).insert((
+                Mesh2d(meshes.add(RegularPolygon::new(BOID_SIZE * 1.5, 3))),
+                MeshMaterial2d(materials.add(ColorMaterial::from_color(PREDATOR_COLOR))),
+            )),
+            _ => return,
+        };
+    }
+}
@@ -0,0 +1,207 @@
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, TAU};
+
+use bevy::{
+    input::mouse::{AccumulatedMouseMotion, AccumulatedMouseScroll},
+    prelude::*,
+};
+
+use crate::{BoidSpawn, FlockConfig, SimRng, SpawnBoids, WorldBounds, WALL_COLOR};
+
+/// Whether boids fly in the XY plane or through a box.
+/// The rules are the same in both, a planar flock is a volume flock held at Z 0.
+/// Insert it before adding the plugins to override the default.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Default)]
+pub enum BoidSpace {
+    #[default]
+    Planar,
+    /// [`WorldBounds`] across and `depth` along Z, centered on Z 0.
+    /// The bounds no longer follow the window, the camera orbits the box instead.
+    Volume { depth: f32 },
+}
+
+impl BoidSpace {
+    pub fn is_volume(&self) -> bool {
+        matches!(self, BoidSpace::Volume { .. })
+    }
+
+    /// Drops the Z component of `vector` when boids stay in the plane.
+    pub fn flatten(&self, vector: Vec3) -> Vec3 {
+        match self {
+            BoidSpace::Planar => vector.with_z(0.),
+            BoidSpace::Volume { .. } => vector,
+        }
+    }
+
+    /// Box the boids live in, unbounded along Z in the plane.
+    pub fn arena(&self, bounds: Rect) -> Arena {
+        let half_depth = match *self {
+            BoidSpace::Planar => f32::INFINITY,
+            BoidSpace::Volume { depth } => depth / 2.,
+        };
+
+        Arena {
+            min: bounds.min.extend(-half_depth),
+            max: bounds.max.extend(half_depth),
+        }
+    }
+}
+
+/// Axis-aligned box boids wrap around, bounce off or steer away from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Arena {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl From<Rect> for Arena {
+    fn from(bounds: Rect) -> Self {
+        BoidSpace::Planar.arena(bounds)
+    }
+}
+
+impl Arena {
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) / 2.
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn contains(&self, point: Vec3) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+
+    /// Grows every side by `margin`, a negative one shrinks the box.
+    pub fn inflate(&self, margin: f32) -> Self {
+        Self {
+            min: self.min - margin,
+            max: self.max + margin,
+        }
+    }
+}
+
+/// Orbits a 3D camera around `focus`: drag with the left mouse button to turn, scroll to zoom.
+#[derive(Component, Debug, Clone, Copy)]
+#[require(Camera3d)]
+pub struct OrbitCamera {
+    pub focus: Vec3,
+    pub distance: f32,
+    // radians around Z, counter-clockwise from +X
+    pub yaw: f32,
+    // radians above the XY plane
+    pub pitch: f32,
+}
+
+impl OrbitCamera {
+    // keeps the camera from flipping over the poles
+    const MAX_PITCH: f32 = FRAC_PI_2 - 0.05;
+
+    pub fn new(focus: Vec3, distance: f32) -> Self {
+        Self {
+            focus,
+            distance,
+            yaw: -FRAC_PI_2,
+            pitch: FRAC_PI_4,
+        }
+    }
+
+    /// Camera transform looking at `focus`, with +Z up.
+    pub fn transform(&self) -> Transform {
+        let direction = Vec3::new(
+            self.pitch.cos() * self.yaw.cos(),
+            self.pitch.cos() * self.yaw.sin(),
+            self.pitch.sin(),
+        );
+
+        Transform::from_translation(self.focus + direction * self.distance)
+            .looking_at(self.focus, Vec3::Z)
+    }
+}
+
+pub(crate) fn planar_space(space: Res<BoidSpace>) -> bool {
+    !space.is_volume()
+}
+
+pub(crate) fn volume_space(space: Res<BoidSpace>) -> bool {
+    space.is_volume()
+}
+
+// Spawns the flock spread through the whole box, flying in every direction.
+pub(crate) fn volume_setup(
+    mut commands: Commands,
+    config: Res<FlockConfig>,
+    space: Res<BoidSpace>,
+    bounds: Res<WorldBounds>,
+    mut rng: ResMut<SimRng>,
+) {
+    let arena = space.arena(bounds.0);
+    let orbit = OrbitCamera::new(arena.center(), arena.size().max_element() * 1.5);
+    commands.spawn((orbit, orbit.transform()));
+    commands.spawn((
+        DirectionalLight::default(),
+        Transform::from_xyz(1., 2., 3.).looking_at(Vec3::ZERO, Vec3::Z),
+    ));
+
+    let spawns = (0..config.boid_count)
+        .map(|_| {
+            let position = arena.min + arena.size() * Vec3::new(rng.f32(), rng.f32(), rng.f32());
+            let heading = rng.f32() * TAU;
+            let pitch = (rng.f32() - 0.5) * FRAC_PI_2;
+
+            let spawn = config.sample(&mut rng, position.xy(), heading);
+            BoidSpawn {
+                z: position.z,
+                pitch,
+                ..spawn
+            }
+        })
+        .collect();
+
+    commands.queue(SpawnBoids(spawns));
+}
+
+pub(crate) fn orbit_camera_system(
+    mouse_input: Option<Res<ButtonInput<MouseButton>>>,
+    motion: Option<Res<AccumulatedMouseMotion>>,
+    scroll: Option<Res<AccumulatedMouseScroll>>,
+    mut cameras: Query<(&mut OrbitCamera, &mut Transform)>,
+) {
+    // radians per pixel dragged
+    const TURN_RATE: f32 = 0.005;
+    // share of the distance zoomed per scrolled step
+    const ZOOM_RATE: f32 = 0.1;
+
+    let dragged = match (mouse_input, motion) {
+        (Some(input), Some(motion)) if input.pressed(MouseButton::Left) => motion.delta,
+        _ => Vec2::ZERO,
+    };
+    let scrolled = scroll.map_or(0., |scroll| scroll.delta.y);
+
+    for (mut orbit, mut transform) in &mut cameras {
+        if dragged != Vec2::ZERO || scrolled != 0. {
+            orbit.yaw -= dragged.x * TURN_RATE;
+            orbit.pitch = (orbit.pitch + dragged.y * TURN_RATE)
+                .clamp(-OrbitCamera::MAX_PITCH, OrbitCamera::MAX_PITCH);
+            orbit.distance = (orbit.distance * (1. - scrolled.clamp(-1., 1.) * ZOOM_RATE)).max(1.);
+        }
+
+        transform.set_if_neq(orbit.transform());
+    }
+}
+
+pub(crate) fn arena_gizmo_system(
+    mut gizmos: Gizmos,
+    space: Res<BoidSpace>,
+    bounds: Res<WorldBounds>,
+) {
+    if !space.is_volume() {
+        return;
+    }
+
+    let arena = space.arena(bounds.0);
+    gizmos.cuboid(
+        Transform::from_translation(arena.center()).with_scale(arena.size()),
+        WALL_COLOR,
+    );
+}
@@ -0,0 +1,265 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use bevy::{ecs::world::Command, prelude::*};
+
+use crate::{
+    Acceleration, AlignmentRule, AttractionRule, BoidMovement, BoidSpace, CohesionRule,
+    FieldOfView, FleeRule, FollowLeaderRule, KinematicLimits, NeighborSelection,
+    ObstacleAvoidanceRule, SeparationRule, Species, Velocity, WanderRule,
+};
+
+pub const BOID_SIZE: f32 = 20.;
+
+/// Radius, factor, field of view and neighbor selection of one of the built-in rules.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RuleParams {
+    pub radius: f32,
+    // between 0.0 and 1.0
+    // 0 means off
+    pub factor: f32,
+    pub fov: FieldOfView,
+    pub selection: NeighborSelection,
+}
+
+impl RuleParams {
+    pub fn new(radius: f32, factor: f32) -> Self {
+        Self {
+            radius,
+            factor,
+            fov: FieldOfView::default(),
+            selection: NeighborSelection::default(),
+        }
+    }
+
+    pub fn with_fov(mut self, fov: FieldOfView) -> Self {
+        self.fov = fov;
+        self
+    }
+
+    pub fn with_selection(mut self, selection: NeighborSelection) -> Self {
+        self.selection = selection;
+        self
+    }
+}
+
+/// Everything needed to spawn one boid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoidSpawn {
+    pub position: Vec2,
+    // only used in `BoidSpace::Volume`
+    pub z: f32,
+    // direction of travel in radians, counter-clockwise from +X
+    pub heading: f32,
+    // radians the direction of travel climbs out of the XY plane, 0 in the plane
+    pub pitch: f32,
+    pub speed: f32,
+    pub rotation_speed: f32,
+    // only used by `MovementModel::Physical`
+    pub limits: KinematicLimits,
+    // how far ahead the boid looks for obstacles
+    pub look_ahead: f32,
+    pub avoidance_factor: f32,
+    // how close a predator may come before the boid flees
+    pub flee_radius: f32,
+    pub flee_factor: f32,
+    // weight of the pull and push of attractors and repellers
+    pub attraction_factor: f32,
+    // how close a leader has to be for the boid to follow it
+    pub follow_radius: f32,
+    pub follow_factor: f32,
+    // 0 flies straight when nothing else steers
+    pub wander_strength: f32,
+    pub wander_rate: f32,
+    pub wander_seed: u64,
+    pub separation: RuleParams,
+    pub alignment: RuleParams,
+    pub cohesion: RuleParams,
+    pub species: Species,
+    pub color: Color,
+}
+
+impl BoidSpawn {
+    /// A boid with the default flock parameters, flying in the XY plane.
+    pub fn new(position: Vec2, heading: f32) -> Self {
+        Self {
+            position,
+            z: 0.,
+            heading,
+            pitch: 0.,
+            speed: 150.,
+            rotation_speed: PI / 2.,
+            limits: KinematicLimits::new(200., 50., 250.),
+            look_ahead: 200.,
+            avoidance_factor: 1.,
+            flee_radius: 250.,
+            flee_factor: 1.,
+            attraction_factor: 1.,
+            follow_radius: 300.,
+            follow_factor: 1.,
+            wander_strength: 0.,
+            wander_rate: 0.5,
+            wander_seed: 0,
+            separation: RuleParams::new(175., 1.),
+            alignment: RuleParams::new(100., 1.),
+            cohesion: RuleParams::new(200., 1.),
+            species: Species::default(),
+            color: Color::WHITE,
+        }
+    }
+
+    pub fn direction(&self) -> Vec3 {
+        (Vec2::from_angle(self.heading) * self.pitch.cos()).extend(self.pitch.sin())
+    }
+}
+
+/// Simulation components of a boid, without any rendering.
+#[derive(Bundle)]
+pub struct BoidBundle {
+    pub transform: Transform,
+    pub movement: BoidMovement,
+    pub velocity: Velocity,
+    pub acceleration: Acceleration,
+    pub limits: KinematicLimits,
+    pub avoidance: ObstacleAvoidanceRule,
+    pub flee: FleeRule,
+    pub attraction: AttractionRule,
+    pub follow: FollowLeaderRule,
+    pub wander: WanderRule,
+    pub separation: SeparationRule,
+    pub alignment: AlignmentRule,
+    pub cohesion: CohesionRule,
+    pub species: Species,
+}
+
+impl BoidBundle {
+    pub fn new(id: usize, spawn: &BoidSpawn) -> Self {
+        Self {
+            transform: Transform::from_translation(spawn.position.extend(spawn.z)).with_rotation(
+                Quat::from_rotation_z(spawn.heading - FRAC_PI_2)
+                    * Quat::from_rotation_x(spawn.pitch),
+            ),
+            movement: BoidMovement::new(id, spawn.speed, spawn.direction(), spawn.rotation_speed),
+            velocity: Velocity(spawn.direction() * spawn.speed),
+            acceleration: Acceleration::default(),
+            limits: spawn.limits,
+            avoidance: ObstacleAvoidanceRule::new(
+                spawn.look_ahead,
+                spawn.avoidance_factor,
+                Vec3::ZERO,
+            ),
+            flee: FleeRule::new(spawn.flee_radius, spawn.flee_factor, Vec3::ZERO),
+            attraction: AttractionRule::new(spawn.attraction_factor, Vec3::ZERO),
+            follow: FollowLeaderRule::new(spawn.follow_radius, spawn.follow_factor, Vec3::ZERO),
+            wander: WanderRule::new(spawn.wander_strength, spawn.wander_rate, spawn.wander_seed),
+            separation: SeparationRule::new(
+                spawn.separation.radius,
+                spawn.separation.factor,
+                Vec3::ZERO,
+            )
+            .with_fov(spawn.separation.fov)
+            .with_selection(spawn.separation.selection),
+            alignment: AlignmentRule::new(
+                spawn.alignment.radius,
+                spawn.alignment.factor,
+                Vec3::ZERO,
+            )
+            .with_fov(spawn.alignment.fov)
+            .with_selection(spawn.alignment.selection),
+            cohesion: CohesionRule::new(spawn.cohesion.radius, spawn.cohesion.factor, Vec3::ZERO)
+                .with_fov(spawn.cohesion.fov)
+                .with_selection(spawn.cohesion.selection),
+            species: spawn.species,
+        }
+    }
+}
+
+/// Spawns boids with consecutive ids.
+/// Queue it as a command, or send it as an event from anywhere in the app.
+/// Boids get a triangle mesh when the app can render one, or a cone in a [`BoidSpace::Volume`].
+#[derive(Event, Debug, Clone)]
+pub struct SpawnBoids(pub Vec<BoidSpawn>);
+
+#[derive(Resource, Default)]
+struct NextBoidId(usize);
+
+// Spawns the simulation components of one boid with the next free id.
+pub(crate) fn spawn_boid(world: &mut World, spawn: &BoidSpawn) -> Entity {
+    let mut next_id = world.get_resource_or_insert_with(NextBoidId::default);
+    let id = next_id.0;
+    next_id.0 += 1;
+
+    let volume = world
+        .get_resource::<BoidSpace>()
+        .is_some_and(BoidSpace::is_volume);
+    let spawn = if volume {
+        *spawn
+    } else {
+        BoidSpawn {
+            z: spawn.z + draw_order_offset(id),
+            ..*spawn
+        }
+    };
+
+    world.spawn(BoidBundle::new(id, &spawn)).id()
+}
+
+// Spreads planar boids over a thin layer so overlapping meshes draw in a stable order.
+// Well below the obstacles and walls, and too thin to change who sees whom.
+fn draw_order_offset(id: usize) -> f32 {
+    const LAYERS: usize = 1000;
+    (id % LAYERS) as f32 / LAYERS as f32
+}
+
+impl Command for SpawnBoids {
+    fn apply(self, world: &mut World) {
+        let volume = world
+            .get_resource::<BoidSpace>()
+            .is_some_and(BoidSpace::is_volume);
+        let render = world.contains_resource::<Assets<Mesh>>()
+            && if volume {
+                world.contains_resource::<Assets<StandardMaterial>>()
+            } else {
+                world.contains_resource::<Assets<ColorMaterial>>()
+            };
+        let mesh = render.then(|| {
+            let mut meshes = world.resource_mut::<Assets<Mesh>>();
+            if volume {
+                meshes.add(Cone::new(BOID_SIZE / 2., BOID_SIZE * 1.5))
+            } else {
+                meshes.add(RegularPolygon::new(BOID_SIZE, 3))
+            }
+        });
+
+        for spawn in self.0 {
+            let boid = spawn_boid(world, &spawn);
+            let Some(mesh) = mesh.clone() else {
+                continue;
+            };
+
+            if volume {
+                let material = world
+                    .resource_mut::<Assets<StandardMaterial>>()
+                    .add(StandardMaterial::from(spawn.color));
+                world
+                    .entity_mut(boid)
+                    .insert((Mesh3d(mesh), MeshMaterial3d(material)));
+            } else {
+                let material = world
+                    .resource_mut::<Assets<ColorMaterial>>()
+                    .add(ColorMaterial::from(spawn.color));
+                world
+                    .entity_mut(boid)
+                    .insert((Mesh2d(mesh), MeshMaterial2d(material)));
+            }
+        }
+    }
+}
+
+pub(crate) fn spawn_boids_event_system(
+    mut commands: Commands,
+    mut spawn_events: EventReader<SpawnBoids>,
+) {
+    for spawn_boids in spawn_events.read() {
+        commands.queue(spawn_boids.clone());
+    }
+}
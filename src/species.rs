@@ -0,0 +1,98 @@
+use bevy::{prelude::*, utils::HashMap};
+
+/// Which kind of boid this is, see [`SpeciesInteractions`].
+/// Boids without one count as species 0.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Species(pub usize);
+
+/// How strongly each of the built-in rules reacts to a neighbor, 0 ignores it.
+/// Weights are never negative, the rules treat a negative one like 0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpeciesInteraction {
+    pub separation: f32,
+    pub alignment: f32,
+    pub cohesion: f32,
+}
+
+impl SpeciesInteraction {
+    pub const FLOCK: Self = Self::new(1., 1., 1.);
+    pub const SEPARATE: Self = Self::new(1., 0., 0.);
+    pub const IGNORE: Self = Self::new(0., 0., 0.);
+
+    /// Negative weights are raised to 0.
+    pub const fn new(separation: f32, alignment: f32, cohesion: f32) -> Self {
+        Self {
+            separation: non_negative(separation),
+            alignment: non_negative(alignment),
+            cohesion: non_negative(cohesion),
+        }
+    }
+}
+
+// also turns NaN into 0
+const fn non_negative(weight: f32) -> f32 {
+    if weight > 0. {
+        weight
+    } else {
+        0.
+    }
+}
+
+/// Species by species matrix of how boids of one species react to neighbors of another.
+/// Pairs that were not set flock with their own kind and use `others` for everyone else.
+#[derive(Resource, Debug, Clone)]
+pub struct SpeciesInteractions {
+    pub others: SpeciesInteraction,
+    matrix: HashMap<(Species, Species), SpeciesInteraction>,
+}
+
+impl Default for SpeciesInteractions {
+    fn default() -> Self {
+        Self::new(SpeciesInteraction::FLOCK)
+    }
+}
+
+impl SpeciesInteractions {
+    pub fn new(others: SpeciesInteraction) -> Self {
+        Self {
+            others,
+            matrix: HashMap::default(),
+        }
+    }
+
+    /// Species keep their distance from each other and only flock with their own kind.
+    pub fn kin_only() -> Self {
+        Self::new(SpeciesInteraction::SEPARATE)
+    }
+
+    /// How boids of species `boid` react to neighbors of species `neighbor`.
+    pub fn get(&self, boid: Species, neighbor: Species) -> SpeciesInteraction {
+        match self.matrix.get(&(boid, neighbor)) {
+            Some(interaction) => *interaction,
+            None if boid == neighbor => SpeciesInteraction::FLOCK,
+            None => self.others,
+        }
+    }
+
+    /// Sets how `boid` reacts to `neighbor`, the other way round is left as is.
+    pub fn set(&mut self, boid: Species, neighbor: Species, interaction: SpeciesInteraction) {
+        self.matrix.insert((boid, neighbor), interaction);
+    }
+
+    /// Sets how both species react to each other.
+    pub fn set_mutual(&mut self, a: Species, b: Species, interaction: SpeciesInteraction) {
+        self.set(a, b, interaction);
+        self.set(b, a, interaction);
+    }
+}
+
+/// A color from the palette of `species`, one of `count` evenly spread hues,
+/// varied a little so boids of the same species can still be told apart.
+pub fn species_color(rng: &mut fastrand::Rng, species: Species, count: usize) -> Color {
+    const HUE_JITTER: f32 = 20.;
+
+    let base_hue = 360. * species.0 as f32 / count.max(1) as f32;
+    let hue = (base_hue + (rng.f32() - 0.5) * HUE_JITTER).rem_euclid(360.);
+
+    Color::hsl(hue, 0.5 + rng.f32() * 0.3, 0.7 + rng.f32() * 0.1)
+}
@@ -0,0 +1,79 @@
+use bevy::prelude::*;
+
+use crate::{BoidMovement, BoidSet, Neighbor, NeighborIndex, Species};
+
+/// A user-defined flocking rule.
+/// Register it with [`SteeringRuleAppExt::add_steering_rule`] and add it to boids
+/// as a component; its velocity is blended with the built-in rules every tick.
+pub trait SteeringRule: Component {
+    /// Neighbors farther than this are not passed to [`SteeringRule::steer`].
+    fn radius(&self) -> f32;
+
+    /// Weight applied to the steered velocity before blending.
+    fn factor(&self) -> f32 {
+        1.0
+    }
+
+    /// Velocity this rule wants for `boid`.
+    /// `neighbors` holds every other boid within [`SteeringRule::radius`].
+    fn steer(&mut self, boid: &Neighbor, movement: &BoidMovement, neighbors: &[Neighbor]) -> Vec3;
+}
+
+/// Velocities produced by custom rules this tick, cleared once the movement systems ran.
+#[derive(Component, Default, Debug)]
+pub struct SteeringVelocities(pub Vec<Vec3>);
+
+pub trait SteeringRuleAppExt {
+    fn add_steering_rule<R: SteeringRule>(&mut self) -> &mut Self;
+}
+
+impl SteeringRuleAppExt for App {
+    fn add_steering_rule<R: SteeringRule>(&mut self) -> &mut Self {
+        // already required when the rule is registered twice
+        let _ = self.try_register_required_components::<R, SteeringVelocities>();
+        self.add_systems(
+            FixedUpdate,
+            steering_rule_system::<R>.in_set(BoidSet::Rules),
+        )
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn steering_rule_system<R: SteeringRule>(
+    index: Res<NeighborIndex>,
+    mut neighbors: Local<Vec<Neighbor>>,
+    mut query: Query<(
+        Entity,
+        &Transform,
+        &BoidMovement,
+        Option<&Species>,
+        &mut R,
+        &mut SteeringVelocities,
+    )>,
+) {
+    for (entity, transform, movement, species, mut rule, mut velocities) in &mut query {
+        let boid = Neighbor {
+            entity,
+            position: transform.translation,
+            heading: transform.rotation * Vec3::Y,
+            species: species.copied().unwrap_or_default(),
+        };
+
+        neighbors.clear();
+        neighbors.extend(
+            index
+                .query(boid.position, rule.radius())
+                .filter(|neighbor| neighbor.entity != entity),
+        );
+
+        let velocity = rule.steer(&boid, movement, &neighbors) * rule.factor();
+        velocities.0.push(velocity);
+    }
+}
+
+// also runs on entities the blending skips, so the velocities never pile up
+pub(crate) fn clear_steering_velocities_system(mut query: Query<&mut SteeringVelocities>) {
+    for mut velocities in &mut query {
+        velocities.0.clear();
+    }
+}
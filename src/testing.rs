@@ -0,0 +1,159 @@
+//! Headless harness for flock tests.
+//! Builds an `App` with the boid plugins on top of `MinimalPlugins`,
+//! spawns a scripted set of boids and steps `FixedUpdate` one tick at a time.
+
+use std::time::Duration;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+
+use crate::{
+    spawn::spawn_boid, BoidSpawn, FlockFilter, MovementPlugin, RuleParams, RulesPlugin, SimRng,
+    WorldBounds,
+};
+
+pub const TICK_RATE: f64 = 60.;
+
+pub struct TestFlock {
+    pub app: App,
+}
+
+impl Default for TestFlock {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl TestFlock {
+    pub fn new(seed: u64) -> Self {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugins((MovementPlugin, RulesPlugin))
+            .insert_resource(SimRng::new(seed))
+            .insert_resource(Time::<Fixed>::from_hz(TICK_RATE))
+            // every update advances time by exactly one fixed tick
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+                1. / TICK_RATE,
+            )));
+
+        // the first update only starts the clocks
+        app.update();
+
+        Self { app }
+    }
+
+    pub fn with_bounds(mut self, bounds: WorldBounds) -> Self {
+        self.app.insert_resource(bounds);
+        self
+    }
+
+    pub fn world(&self) -> &World {
+        self.app.world()
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        self.app.world_mut()
+    }
+
+    pub fn bounds(&self) -> Rect {
+        self.world().resource::<WorldBounds>().0
+    }
+
+    /// Spawns one boid without a mesh, taking the next id like [`SpawnBoids`](crate::SpawnBoids).
+    pub fn spawn(&mut self, spawn: BoidSpawn) -> Entity {
+        spawn_boid(self.world_mut(), &spawn)
+    }
+
+    /// Spawns `count` boids at random positions and headings inside `area`, drawn from [`SimRng`].
+    pub fn spawn_random(&mut self, count: usize, area: Rect) -> Vec<Entity> {
+        (0..count)
+            .map(|_| {
+                let mut rng = self.world_mut().resource_mut::<SimRng>();
+                let position = area.min + area.size() * Vec2::new(rng.f32(), rng.f32());
+                let heading = rng.f32() * std::f32::consts::TAU;
+
+                self.spawn(BoidSpawn::new(position, heading))
+            })
+            .collect()
+    }
+
+    /// Runs `ticks` fixed updates.
+    pub fn step(&mut self, ticks: usize) {
+        for _ in 0..ticks {
+            self.app.update();
+        }
+    }
+
+    pub fn positions(&mut self) -> Vec<Vec2> {
+        let world = self.world_mut();
+        world
+            .query_filtered::<&Transform, FlockFilter>()
+            .iter(world)
+            .map(|transform| transform.translation.xy())
+            .collect()
+    }
+
+    pub fn translations(&mut self) -> Vec<Vec3> {
+        let world = self.world_mut();
+        world
+            .query_filtered::<&Transform, FlockFilter>()
+            .iter(world)
+            .map(|transform| transform.translation)
+            .collect()
+    }
+
+    pub fn headings(&mut self) -> Vec<Vec2> {
+        let world = self.world_mut();
+        world
+            .query_filtered::<&Transform, FlockFilter>()
+            .iter(world)
+            .map(|transform| (transform.rotation * Vec3::Y).xy())
+            .collect()
+    }
+
+    pub fn transforms(&mut self) -> Vec<Transform> {
+        let world = self.world_mut();
+        world
+            .query_filtered::<&Transform, FlockFilter>()
+            .iter(world)
+            .copied()
+            .collect()
+    }
+}
+
+/// Smallest distance between any two points.
+pub fn min_pairwise_distance(points: &[Vec2]) -> f32 {
+    points
+        .iter()
+        .enumerate()
+        .flat_map(|(i, a)| points[i + 1..].iter().map(move |b| a.distance(*b)))
+        .fold(f32::INFINITY, f32::min)
+}
+
+/// Turns every rule but separation off.
+pub fn separation_only(spawn: BoidSpawn) -> BoidSpawn {
+    BoidSpawn {
+        alignment: RuleParams::new(spawn.alignment.radius, 0.),
+        cohesion: RuleParams::new(spawn.cohesion.radius, 0.),
+        ..spawn
+    }
+}
+
+/// Turns every rule but alignment off.
+pub fn alignment_only(spawn: BoidSpawn) -> BoidSpawn {
+    BoidSpawn {
+        separation: RuleParams::new(spawn.separation.radius, 0.),
+        cohesion: RuleParams::new(spawn.cohesion.radius, 0.),
+        ..spawn
+    }
+}
+
+/// One minus the length of the mean unit heading.
+/// 0 when every heading is the same, close to 1 when they cancel out.
+pub fn heading_variance(headings: &[Vec2]) -> f32 {
+    let mean: Vec2 = headings
+        .iter()
+        .map(|heading| heading.normalize())
+        .sum::<Vec2>()
+        / headings.len() as f32;
+    1. - mean.length()
+}
@@ -0,0 +1,78 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+use crate::BoidMovement;
+
+/// Smooth 1D Perlin noise between -1 and 1, the same for the same `seed` and `x`.
+pub fn perlin_noise(seed: u64, x: f32) -> f32 {
+    let cell = x.floor();
+    let t = x - cell;
+    let cell = cell as i64;
+
+    let left = gradient(seed, cell) * t;
+    let right = gradient(seed, cell + 1) * (t - 1.);
+    let fade = t * t * t * (t * (t * 6. - 15.) + 10.);
+
+    // 1D gradient noise peaks at 0.5
+    (left + (right - left) * fade) * 2.
+}
+
+// splitmix64 of the lattice point, mapped to a gradient between -1 and 1
+fn gradient(seed: u64, cell: i64) -> f32 {
+    let mut z = seed
+        .wrapping_add(cell as u64)
+        .wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+
+    (z >> 40) as f32 / (1u64 << 23) as f32 - 1.
+}
+
+/// Reynolds' wander: aims at a point on a circle ahead of the boid,
+/// moved around the circle by Perlin noise instead of random jitter.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct WanderRule {
+    // radius of the wander circle one unit ahead, 0 means off
+    pub strength: f32,
+    // how many noise cells the target crosses per second, higher turns more often
+    pub rate: f32,
+    pub seed: u64,
+    pub velocity: Vec3,
+}
+
+impl WanderRule {
+    pub fn new(strength: f32, rate: f32, seed: u64) -> Self {
+        Self {
+            strength,
+            rate,
+            seed,
+            velocity: Vec3::ZERO,
+        }
+    }
+}
+
+pub(crate) fn wander_system(
+    time: Res<Time>,
+    mut boids: Query<(&Transform, &BoidMovement, &mut WanderRule)>,
+) {
+    let elapsed = time.elapsed_secs();
+
+    boids
+        .par_iter_mut()
+        .for_each(|(transform, movement, mut wander)| {
+            if wander.strength <= 0. {
+                wander.velocity = Vec3::ZERO;
+                return;
+            }
+
+            // the circle lies in the boid's own plane, the XY plane for planar flocks
+            let heading = transform.rotation * Vec3::Y;
+            let up = transform.rotation * Vec3::Z;
+            let angle = perlin_noise(wander.seed, elapsed * wander.rate) * PI;
+            let target = heading + Quat::from_axis_angle(up, angle) * heading * wander.strength;
+
+            wander.velocity = target.normalize_or(heading) * movement.speed;
+        });
+}
@@ -0,0 +1,109 @@
+use bevy::prelude::*;
+use boids_rs::{
+    BoidDistributions, BoidSpawn, Distribution, FieldOfView, KinematicLimits, NeighborSelection,
+    RuleParams,
+};
+
+#[test]
+fn fixed_always_returns_its_value() {
+    let mut rng = fastrand::Rng::with_seed(1);
+
+    for _ in 0..100 {
+        assert_eq!(Distribution::Fixed(150.).sample(&mut rng), 150.);
+    }
+}
+
+#[test]
+fn uniform_stays_in_range() {
+    let mut rng = fastrand::Rng::with_seed(1);
+    let distribution = Distribution::Uniform { min: 50., max: 80. };
+
+    for _ in 0..1000 {
+        let value = distribution.sample(&mut rng);
+        assert!((50. ..=80.).contains(&value), "{value}");
+    }
+}
+
+#[test]
+fn normal_is_clamped_and_centered() {
+    let mut rng = fastrand::Rng::with_seed(1);
+    let distribution = Distribution::Normal {
+        mean: 150.,
+        std_dev: 30.,
+        min: 100.,
+        max: 200.,
+    };
+
+    let samples: Vec<f32> = (0..10_000).map(|_| distribution.sample(&mut rng)).collect();
+    let mean = samples.iter().sum::<f32>() / samples.len() as f32;
+
+    assert!(samples.iter().all(|value| (100. ..=200.).contains(value)));
+    assert!((mean - 150.).abs() < 2., "{mean}");
+}
+
+#[test]
+fn default_distributions_spawn_default_boids() {
+    let mut rng = fastrand::Rng::with_seed(1);
+
+    let spawn = BoidDistributions::default().sample(&mut rng, Vec2::ONE, 1.);
+    let expected = BoidSpawn::new(Vec2::ONE, 1.);
+
+    assert_eq!(spawn.speed, expected.speed);
+    assert_eq!(spawn.separation, expected.separation);
+    assert_eq!(spawn.alignment, expected.alignment);
+    assert_eq!(spawn.cohesion, expected.cohesion);
+}
+
+#[test]
+fn normal_accepts_bounds_in_either_order() {
+    let mut rng = fastrand::Rng::with_seed(1);
+    let built = Distribution::normal(150., 30., 200., 100.);
+    let literal = Distribution::Normal {
+        mean: 150.,
+        std_dev: 30.,
+        min: 200.,
+        max: 100.,
+    };
+
+    assert_eq!(
+        built,
+        Distribution::Normal {
+            mean: 150.,
+            std_dev: 30.,
+            min: 100.,
+            max: 200.,
+        }
+    );
+    for _ in 0..1000 {
+        assert!((100. ..=200.).contains(&literal.sample(&mut rng)));
+    }
+}
+
+#[test]
+fn sampled_boids_keep_the_template_settings() {
+    let mut rng = fastrand::Rng::with_seed(1);
+    let fov = FieldOfView::new(3., 0.5);
+    let template = BoidSpawn {
+        separation: RuleParams::new(50., 1.).with_fov(fov),
+        cohesion: RuleParams::new(200., 1.).with_selection(NeighborSelection::Nearest { k: 7 }),
+        limits: KinematicLimits::new(100., 10., 300.),
+        flee_radius: 400.,
+        ..BoidSpawn::new(Vec2::ZERO, 0.)
+    };
+    let distributions = BoidDistributions {
+        separation_radius: Distribution::uniform(40., 60.),
+        ..BoidDistributions::from_template(template)
+    };
+
+    let spawn = distributions.sample(&mut rng, Vec2::ONE, 1.);
+
+    assert_eq!((spawn.position, spawn.heading), (Vec2::ONE, 1.));
+    assert!((40. ..=60.).contains(&spawn.separation.radius));
+    assert_eq!(spawn.separation.fov, fov);
+    assert_eq!(
+        spawn.cohesion.selection,
+        NeighborSelection::Nearest { k: 7 }
+    );
+    assert_eq!(spawn.limits, template.limits);
+    assert_eq!(spawn.flee_radius, 400.);
+}
@@ -0,0 +1,456 @@
+use std::{
+    f32::consts::{PI, TAU},
+    time::Duration,
+};
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use boids_rs::{
+    testing::{
+        alignment_only, heading_variance, min_pairwise_distance, separation_only, TestFlock,
+    },
+    AlignmentRule, BlendMode, BoidBundle, BoidMovement, BoidSpace, BoidSpawn, BoundaryMode,
+    CohesionRule, CursorPlugin, FieldOfView, KinematicLimits, MovementModel, NeighborSelection,
+    RuleParams, RulesPlugin, SeparationRule, SimRng, SpawnBoids, Velocity, WorldBounds,
+};
+
+fn grid(columns: usize, rows: usize, spacing: f32) -> Vec<Vec2> {
+    let offset = Vec2::new(columns as f32 - 1., rows as f32 - 1.) * spacing / 2.;
+    (0..rows)
+        .flat_map(|r| (0..columns).map(move |c| Vec2::new(c as f32, r as f32) * spacing - offset))
+        .collect()
+}
+
+#[test]
+fn separation_keeps_boids_apart() {
+    let mut flock = TestFlock::default();
+    for (i, position) in grid(3, 3, 10.).into_iter().enumerate() {
+        flock.spawn(separation_only(BoidSpawn::new(position, i as f32)));
+    }
+
+    let before = min_pairwise_distance(&flock.positions());
+    flock.step(120);
+    let after = min_pairwise_distance(&flock.positions());
+
+    assert!(
+        after > before * 3.,
+        "min distance went from {before} to {after}"
+    );
+}
+
+#[test]
+fn alignment_reduces_heading_variance() {
+    let mut flock = TestFlock::default();
+    for (i, position) in grid(4, 4, 15.).into_iter().enumerate() {
+        flock.spawn(alignment_only(BoidSpawn::new(position, i as f32 * 2.4)));
+    }
+
+    let before = heading_variance(&flock.headings());
+    flock.step(120);
+    let after = heading_variance(&flock.headings());
+
+    assert!(
+        after < before / 2.,
+        "heading variance went from {before} to {after}"
+    );
+}
+
+#[test]
+fn wrapping_keeps_boids_inside_bounds() {
+    let mut flock = TestFlock::default().with_bounds(WorldBounds::from_size(Vec2::splat(400.)));
+    let bounds = flock.bounds();
+    flock.spawn_random(32, bounds);
+
+    for _ in 0..10 {
+        flock.step(30);
+        for position in flock.positions() {
+            assert!(bounds.contains(position), "{position} left {bounds:?}");
+        }
+    }
+}
+
+#[test]
+fn same_seed_gives_identical_runs() {
+    let run = |seed| {
+        let mut flock = TestFlock::new(seed);
+        let bounds = flock.bounds();
+        flock.spawn_random(64, bounds);
+        flock.step(120);
+        flock.transforms()
+    };
+
+    assert_eq!(run(7), run(7));
+    assert_ne!(run(7), run(8));
+}
+
+#[test]
+fn blend_modes_ignore_idle_rules() {
+    let velocities = [Vec3::new(3., 0., 0.), Vec3::ZERO, Vec3::new(0., 4., 0.)];
+
+    assert_eq!(BlendMode::Weighted.blend(velocities), Vec3::new(3., 4., 0.));
+    assert_eq!(
+        BlendMode::Normalized.blend(velocities),
+        Vec3::new(1., 1., 0.)
+    );
+}
+
+#[test]
+fn prioritized_blend_spends_budget_in_order() {
+    let velocities = [
+        Vec3::new(3., 0., 0.),
+        Vec3::new(0., 4., 0.),
+        Vec3::new(-10., 0., 0.),
+    ];
+
+    let velocity = BlendMode::Prioritized { budget: 5. }.blend(velocities);
+
+    assert_eq!(velocity, Vec3::new(3., 2., 0.));
+}
+
+#[test]
+fn step_runs_one_fixed_tick_per_call() {
+    let mut flock = TestFlock::default();
+    flock.spawn(BoidSpawn::new(Vec2::ZERO, 0.));
+
+    flock.step(60);
+
+    let position = flock.positions()[0];
+    assert!(
+        (position - Vec2::new(150., 0.)).length() < 1e-3,
+        "{position}"
+    );
+}
+
+#[test]
+fn spawn_boids_event_spawns_headless_boids() {
+    let mut flock = TestFlock::default();
+    let spawns = (0..5)
+        .map(|i| BoidSpawn::new(Vec2::new(i as f32 * 50., 0.), 0.))
+        .collect();
+
+    flock.spawn(BoidSpawn::new(Vec2::new(0., 100.), 0.));
+    flock.world_mut().send_event(SpawnBoids(spawns));
+    flock.spawn(BoidSpawn::new(Vec2::new(0., 200.), 0.));
+    flock.step(1);
+
+    let world = flock.world_mut();
+    let mut ids: Vec<usize> = world
+        .query::<&BoidMovement>()
+        .iter(world)
+        .map(|movement| movement.id)
+        .collect();
+    ids.sort();
+    assert_eq!(ids, vec![0, 1, 2, 3, 4, 5, 6]);
+
+    // every planar boid is drawn at its own depth
+    let mut depths: Vec<f32> = flock.translations().iter().map(|t| t.z).collect();
+    depths.sort_by(f32::total_cmp);
+    depths.dedup();
+    assert_eq!(depths.len(), 7);
+    assert!(depths.iter().all(|z| (0. ..1.).contains(z)));
+}
+
+#[test]
+fn physical_model_changes_speed_within_limits() {
+    let mut flock = TestFlock::default();
+    flock.world_mut().insert_resource(MovementModel::Physical);
+    flock.world_mut().insert_resource(BlendMode::Weighted);
+    flock.spawn_random(48, Rect::from_center_size(Vec2::ZERO, Vec2::splat(300.)));
+
+    flock.step(120);
+
+    let world = flock.world_mut();
+    let speeds: Vec<(f32, KinematicLimits)> = world
+        .query::<(&Velocity, &KinematicLimits)>()
+        .iter(world)
+        .map(|(velocity, limits)| (velocity.length(), *limits))
+        .collect();
+
+    for (speed, limits) in &speeds {
+        assert!(
+            (limits.min_speed - 1e-3..=limits.max_speed + 1e-3).contains(speed),
+            "{speed} outside {limits:?}"
+        );
+    }
+    assert!(speeds.iter().any(|(speed, _)| (speed - 150.).abs() > 1.));
+}
+
+#[test]
+fn speed_limits_out_of_order_do_not_panic() {
+    assert_eq!(
+        KinematicLimits::new(100., 300., 200.),
+        KinematicLimits::new(100., 200., 300.)
+    );
+
+    let mut flock = TestFlock::default();
+    flock.world_mut().insert_resource(MovementModel::Physical);
+    let boid = flock.spawn(BoidSpawn::new(Vec2::ZERO, 0.));
+    flock.world_mut().entity_mut(boid).insert(KinematicLimits {
+        max_force: 100.,
+        min_speed: 300.,
+        max_speed: 200.,
+    });
+
+    flock.step(10);
+
+    let speed = flock.world().get::<Velocity>(boid).unwrap().length();
+    assert!((speed - 200.).abs() < 1e-3, "{speed}");
+}
+
+#[test]
+fn boids_on_the_same_spot_stay_finite() {
+    let mut flock = TestFlock::default();
+    // no draw depth offset in a volume, the boids really coincide
+    flock
+        .world_mut()
+        .insert_resource(BoidSpace::Volume { depth: 400. });
+    flock.world_mut().insert_resource(MovementModel::Physical);
+    flock.world_mut().insert_resource(BlendMode::Weighted);
+    for heading in [0., 1., 2.] {
+        flock.spawn(BoidSpawn::new(Vec2::new(50., 50.), heading));
+    }
+
+    flock.step(30);
+
+    assert!(flock
+        .transforms()
+        .iter()
+        .all(|transform| transform.is_finite()));
+}
+
+#[test]
+fn bounce_keeps_boids_inside_bounds() {
+    let mut flock = TestFlock::default().with_bounds(WorldBounds::from_size(Vec2::splat(400.)));
+    flock.world_mut().insert_resource(BoundaryMode::Bounce);
+    let bounds = flock.bounds();
+    flock.spawn_random(32, bounds);
+
+    for _ in 0..10 {
+        flock.step(30);
+        for position in flock.positions() {
+            assert!(bounds.contains(position), "{position} left {bounds:?}");
+        }
+    }
+}
+
+#[test]
+fn steer_away_turns_boids_back_before_they_go_far() {
+    let margin = 150.;
+    let mut flock = TestFlock::default().with_bounds(WorldBounds::from_size(Vec2::splat(800.)));
+    flock
+        .world_mut()
+        .insert_resource(BoundaryMode::SteerAway { margin });
+    let bounds = flock.bounds();
+    flock.spawn_random(32, bounds.inflate(-margin));
+
+    let limit = bounds.inflate(margin);
+    for _ in 0..20 {
+        flock.step(30);
+        for position in flock.positions() {
+            assert!(limit.contains(position), "{position} left {limit:?}");
+        }
+    }
+}
+
+#[test]
+fn open_boundary_lets_boids_leave() {
+    let mut flock = TestFlock::default().with_bounds(WorldBounds::from_size(Vec2::splat(100.)));
+    flock.world_mut().insert_resource(BoundaryMode::Open);
+    flock.spawn(BoidSpawn::new(Vec2::ZERO, 0.));
+
+    flock.step(60);
+
+    assert!(!flock.bounds().contains(flock.positions()[0]));
+}
+
+#[test]
+fn field_of_view_hides_boids_behind() {
+    let mut flock = TestFlock::default();
+    let half_view = RuleParams::new(175., 1.).with_fov(FieldOfView::new(PI, 0.));
+    let blind = flock.spawn(BoidSpawn {
+        separation: half_view,
+        ..separation_only(BoidSpawn::new(Vec2::ZERO, 0.))
+    });
+    let seeing = flock.spawn(separation_only(BoidSpawn::new(Vec2::new(-20., 0.), 0.)));
+
+    flock.step(1);
+
+    let world = flock.world();
+    assert_eq!(
+        world.get::<SeparationRule>(blind).unwrap().velocity,
+        Vec3::ZERO
+    );
+    assert_ne!(
+        world.get::<SeparationRule>(seeing).unwrap().velocity,
+        Vec3::ZERO
+    );
+}
+
+#[test]
+fn nearest_selection_reaches_beyond_the_radius() {
+    let mut flock = TestFlock::default();
+    let nearest = RuleParams::new(100., 1.).with_selection(NeighborSelection::Nearest { k: 1 });
+    let topological = flock.spawn(BoidSpawn {
+        alignment: nearest,
+        ..alignment_only(BoidSpawn::new(Vec2::ZERO, 0.))
+    });
+    let metric = flock.spawn(alignment_only(BoidSpawn::new(Vec2::new(500., 0.), PI)));
+
+    flock.step(1);
+
+    let world = flock.world();
+    assert_ne!(
+        world.get::<AlignmentRule>(topological).unwrap().velocity,
+        Vec3::ZERO
+    );
+    assert_eq!(
+        world.get::<AlignmentRule>(metric).unwrap().velocity,
+        Vec3::ZERO
+    );
+}
+
+#[test]
+fn rules_run_without_the_movement_plugin() {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins((RulesPlugin, CursorPlugin))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+            1. / 30.,
+        )));
+    app.world_mut()
+        .spawn(BoidBundle::new(0, &BoidSpawn::new(Vec2::ZERO, 0.)));
+    // a few fixed ticks, the first update only starts the clocks
+    for _ in 0..4 {
+        app.update();
+    }
+}
+
+#[test]
+fn perception_counts_each_boid_once_in_a_small_wrapped_arena() {
+    // a radius over half the arena reaches the neighbor on both sides of the seam
+    let mut flock = TestFlock::default().with_bounds(WorldBounds::from_size(Vec2::splat(200.)));
+    let spawn = |x: f32| {
+        let mut spawn = separation_only(BoidSpawn::new(Vec2::new(x, 0.), 0.));
+        spawn.separation = RuleParams::new(160., 1.);
+        spawn
+    };
+    let boid = flock.spawn(spawn(0.));
+    flock.spawn(spawn(50.));
+
+    flock.step(1);
+
+    let speed = flock.world().get::<BoidMovement>(boid).unwrap().speed;
+    let velocity = flock.world().get::<SeparationRule>(boid).unwrap().velocity;
+    let expected = Vec3::NEG_X * (160. - 50.) / 160. * speed;
+    assert!(
+        velocity.distance(expected) < 1e-2,
+        "{velocity} != {expected}"
+    );
+}
+
+// Separation, alignment and cohesion each worked out on their own over the whole flock,
+// the way separate passes without the shared neighbor search would.
+fn reference_rules(boids: &[(Vec3, Vec3, f32)], boid: usize, rules: [RuleParams; 3]) -> [Vec3; 3] {
+    let (position, heading, speed) = boids[boid];
+    let neighbors = |rule: RuleParams| {
+        boids
+            .iter()
+            .enumerate()
+            .filter(move |(i, _)| *i != boid)
+            .map(move |(_, (other, other_heading, _))| (*other - position, *other_heading))
+            .filter(move |(offset, _)| {
+                offset.length() <= rule.radius && rule.fov.contains(heading, *offset)
+            })
+    };
+    let weight = |rule: RuleParams, distance: f32| (rule.radius - distance) / rule.radius;
+    let mean = |velocities: Vec<Vec3>| {
+        let count = velocities.len().max(1) as f32;
+        velocities.into_iter().sum::<Vec3>() / count
+    };
+
+    let [separation, alignment, cohesion] = rules;
+    let separation_velocity = mean(
+        neighbors(separation)
+            .map(|(offset, _)| {
+                -offset.normalize_or_zero() * weight(separation, offset.length()) * speed
+            })
+            .collect(),
+    );
+    let alignment_velocity = mean(
+        neighbors(alignment)
+            .map(|(offset, other_heading)| {
+                other_heading.normalize_or_zero() * weight(alignment, offset.length()) * speed
+            })
+            .collect(),
+    );
+    let offsets: Vec<Vec3> = neighbors(cohesion).map(|(offset, _)| offset).collect();
+    let cohesion_velocity = if offsets.is_empty() {
+        Vec3::ZERO
+    } else {
+        let to_center = mean(offsets);
+        to_center.normalize_or_zero() * weight(cohesion, to_center.length()) * speed
+    };
+
+    [
+        separation_velocity * separation.factor,
+        alignment_velocity * alignment.factor,
+        cohesion_velocity * cohesion.factor,
+    ]
+}
+
+#[test]
+fn single_pass_perception_matches_separate_rule_passes() {
+    let rules = [
+        RuleParams::new(40., 0.9),
+        RuleParams::new(70., 0.6).with_fov(FieldOfView::new(PI * 1.5, 0.)),
+        RuleParams::new(110., 0.3).with_fov(FieldOfView::new(TAU, PI / 2.)),
+    ];
+    let mut flock = TestFlock::new(7);
+    for _ in 0..200 {
+        let mut rng = flock.world_mut().resource_mut::<SimRng>();
+        let position = Vec2::new(rng.f32(), rng.f32()) * 600. - 300.;
+        let heading = rng.f32() * TAU;
+        flock.spawn(BoidSpawn {
+            separation: rules[0],
+            alignment: rules[1],
+            cohesion: rules[2],
+            ..BoidSpawn::new(position, heading)
+        });
+    }
+
+    let world = flock.world_mut();
+    let entities: Vec<Entity> = world
+        .query::<(Entity, &BoidMovement)>()
+        .iter(world)
+        .map(|(entity, _)| entity)
+        .collect();
+    let boids: Vec<(Vec3, Vec3, f32)> = entities
+        .iter()
+        .map(|&entity| {
+            let transform = world.get::<Transform>(entity).unwrap();
+            let speed = world.get::<BoidMovement>(entity).unwrap().speed;
+            (transform.translation, transform.rotation * Vec3::Y, speed)
+        })
+        .collect();
+
+    flock.step(1);
+
+    let world = flock.world();
+    let mut seen = 0;
+    for (boid, &entity) in entities.iter().enumerate() {
+        let expected = reference_rules(&boids, boid, rules);
+        let actual = [
+            world.get::<SeparationRule>(entity).unwrap().velocity,
+            world.get::<AlignmentRule>(entity).unwrap().velocity,
+            world.get::<CohesionRule>(entity).unwrap().velocity,
+        ];
+        for (actual, expected) in actual.into_iter().zip(expected) {
+            assert!(
+                actual.distance(expected) <= 1e-3 * expected.length().max(1.),
+                "boid {boid}: {actual} != {expected}"
+            );
+            seen += usize::from(expected != Vec3::ZERO);
+        }
+    }
+    // the flock is dense enough for every rule to see neighbors
+    assert!(seen > entities.len() * 2, "{seen}");
+}
@@ -0,0 +1,96 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+use boids_rs::{testing::TestFlock, BoidSpawn, FlowField};
+
+#[test]
+fn flow_field_blends_between_cell_centers() {
+    let area = Rect::new(0., 0., 200., 100.);
+    let field = FlowField::new(area, 2, 1, vec![Vec2::X * 10., Vec2::X * 30.]);
+
+    assert_eq!(field.sample(Vec2::new(50., 50.)), Vec2::X * 10.);
+    assert_eq!(field.sample(Vec2::new(100., 50.)), Vec2::X * 20.);
+    assert_eq!(field.sample(Vec2::new(190., 10.)), Vec2::X * 30.);
+    assert_eq!(field.sample(Vec2::new(250., 50.)), Vec2::ZERO);
+
+    let shear = FlowField::shear(area, 8, 40.);
+    assert!(shear.sample(Vec2::new(100., 95.)).x > 0.);
+    assert!(shear.sample(Vec2::new(100., 5.)).x < 0.);
+
+    let vortex = FlowField::vortex(Rect::new(-100., -100., 100., 100.), 16, 40.);
+    assert!(vortex.sample(Vec2::new(90., 0.)).y > 0.);
+    assert!(vortex.sample(Vec2::new(0., 90.)).x < 0.);
+}
+
+#[test]
+fn flow_field_reads_directions_from_red_and_green() {
+    // top pixel flows up, bottom pixel flows left
+    let image = Image::new(
+        Extent3d {
+            width: 1,
+            height: 2,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        vec![128, 255, 0, 255, 0, 128, 0, 255],
+        TextureFormat::Rgba8Unorm,
+        RenderAssetUsages::MAIN_WORLD,
+    );
+    let field = FlowField::from_image(&image, Rect::new(0., 0., 100., 200.), 50.).unwrap();
+
+    assert_eq!((field.cols(), field.rows()), (1, 2));
+    assert!(field.get(0, 1).distance(Vec2::Y * 50.) < 1.);
+    assert!(field.get(0, 0).distance(Vec2::NEG_X * 50.) < 1.);
+}
+
+#[test]
+fn flow_field_round_trips_through_a_png() {
+    const STRENGTH: f32 = 40.;
+    let area = Rect::new(0., 0., 300., 200.);
+    let field = FlowField::from_fn(area, 3, 2, |position| {
+        Vec2::new(position.x / 150. - 1., 1. - position.y / 100.) * STRENGTH
+    });
+
+    // one pixel per cell, top row first, 128 in a channel is still
+    let channel = |value: f32| ((value / STRENGTH + 1.) / 2. * 255.).round() as u8;
+    let mut png = image::RgbaImage::new(3, 2);
+    for (x, y, pixel) in png.enumerate_pixels_mut() {
+        let flow = field.get(x as usize, 1 - y as usize);
+        *pixel = image::Rgba([channel(flow.x), channel(flow.y), 0, 255]);
+    }
+    let mut bytes = Vec::new();
+    png.write_to(
+        &mut std::io::Cursor::new(&mut bytes),
+        image::ImageFormat::Png,
+    )
+    .unwrap();
+
+    let decoded = FlowField::from_png(&bytes, area, STRENGTH).unwrap();
+
+    assert_eq!((decoded.cols(), decoded.rows()), (3, 2));
+    for (col, row) in [(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)] {
+        let (expected, found) = (field.get(col, row), decoded.get(col, row));
+        assert!(
+            expected.distance(found) < STRENGTH * 0.01,
+            "{expected} {found}"
+        );
+    }
+    assert!(FlowField::from_png(b"not a png", area, STRENGTH).is_err());
+}
+
+#[test]
+fn wind_carries_boids_without_turning_them() {
+    let mut flock = TestFlock::default();
+    flock.world_mut().insert_resource(FlowField::uniform(
+        Rect::new(-1000., -1000., 1000., 1000.),
+        Vec2::Y * 60.,
+    ));
+    flock.spawn(BoidSpawn::new(Vec2::ZERO, 0.));
+
+    flock.step(60);
+
+    assert!((flock.positions()[0].y - 60.).abs() < 1.);
+    assert!(flock.headings()[0].abs_diff_eq(Vec2::X, 1e-4));
+}
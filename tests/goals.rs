@@ -0,0 +1,125 @@
+use bevy::prelude::*;
+use boids_rs::{
+    testing::TestFlock, Attractor, BoidSpawn, Falloff, PathFollowRule, Repeller, SeekRule,
+};
+
+#[test]
+fn falloff_fades_to_zero_at_radius() {
+    for falloff in [Falloff::Constant, Falloff::Linear, Falloff::Quadratic] {
+        assert_eq!(falloff.weight(0., 100.), 1.);
+        assert_eq!(falloff.weight(101., 100.), 0.);
+    }
+
+    assert_eq!(Falloff::Constant.weight(50., 100.), 1.);
+    assert_eq!(Falloff::Linear.weight(50., 100.), 0.5);
+    assert_eq!(Falloff::Quadratic.weight(50., 100.), 0.25);
+}
+
+#[test]
+fn attractors_pull_and_repellers_push() {
+    let above = Transform::from_xyz(0., 300., 0.);
+
+    let mut attracted = TestFlock::default();
+    attracted
+        .world_mut()
+        .spawn((Attractor::new(1., 500., Falloff::Linear), above));
+    attracted.spawn(BoidSpawn::new(Vec2::ZERO, 0.));
+    attracted.step(30);
+
+    let mut repelled = TestFlock::default();
+    repelled
+        .world_mut()
+        .spawn((Repeller::new(1., 500., Falloff::Linear), above));
+    repelled.spawn(BoidSpawn::new(Vec2::ZERO, 0.));
+    repelled.step(30);
+
+    assert!(attracted.positions()[0].y > 10.);
+    assert!(repelled.positions()[0].y < -10.);
+}
+
+#[test]
+fn seeking_boids_reach_their_goal() {
+    let goal = Vec2::new(0., -500.);
+    let mut flock = TestFlock::default();
+    let boid = flock.spawn(BoidSpawn::new(Vec2::ZERO, 0.));
+    flock
+        .world_mut()
+        .entity_mut(boid)
+        .insert(SeekRule::new(goal, 100., 1.));
+
+    let mut closest = f32::INFINITY;
+    for _ in 0..300 {
+        flock.step(1);
+        closest = closest.min(flock.positions()[0].distance(goal));
+    }
+
+    assert!(closest < 50., "closest {closest}");
+}
+
+#[test]
+fn path_followers_visit_waypoints_in_order() {
+    let square = [
+        Vec2::new(-400., -400.),
+        Vec2::new(400., -400.),
+        Vec2::new(400., 400.),
+        Vec2::new(-400., 400.),
+    ];
+    let mut flock = TestFlock::default();
+    let boid = flock.spawn(BoidSpawn::new(square[0], 0.));
+    flock
+        .world_mut()
+        .entity_mut(boid)
+        .insert(PathFollowRule::new(square, true, 40., 1.));
+
+    let mut visited = vec![];
+    for _ in 0..1500 {
+        flock.step(1);
+        let next = flock
+            .world()
+            .get::<PathFollowRule>(boid)
+            .unwrap()
+            .next_waypoint();
+        if visited.last() != Some(&next) {
+            visited.push(next);
+        }
+    }
+
+    assert_eq!(visited[..5], [1, 2, 3, 0, 1]);
+}
+
+#[test]
+fn editing_a_path_keeps_followers_on_it() {
+    let square = [
+        Vec2::new(-400., -400.),
+        Vec2::new(400., -400.),
+        Vec2::new(400., 400.),
+        Vec2::new(-400., 400.),
+    ];
+    let mut flock = TestFlock::default();
+    let boid = flock.spawn(BoidSpawn::new(square[0], 0.));
+    flock
+        .world_mut()
+        .entity_mut(boid)
+        .insert(PathFollowRule::new(square, true, 40., 1.));
+
+    let path = |flock: &TestFlock| flock.world().get::<PathFollowRule>(boid).unwrap().clone();
+    // onto the closing segment, back to the first waypoint
+    for _ in 0..1500 {
+        flock.step(1);
+        if path(&flock).next_waypoint() == 0 {
+            break;
+        }
+    }
+    assert_eq!(path(&flock).next_waypoint(), 0);
+
+    let mut rule = flock.world_mut().get_mut::<PathFollowRule>(boid).unwrap();
+    rule.looped = false;
+    flock.step(1);
+    assert_eq!(path(&flock).next_waypoint(), 3);
+
+    let mut rule = flock.world_mut().get_mut::<PathFollowRule>(boid).unwrap();
+    rule.waypoints.truncate(2);
+    flock.step(1);
+    assert_eq!(path(&flock).next_waypoint(), 1);
+    assert!(path(&flock).velocity.is_finite());
+}
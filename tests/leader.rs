@@ -0,0 +1,92 @@
+use bevy::prelude::*;
+use boids_rs::{
+    testing::TestFlock, BoidSpawn, FollowLeaderRule, KeyboardSteering, Leader, LeaderScript,
+    RuleParams, WorldBounds,
+};
+
+// a leader that only goes where it is steered
+fn leader_spawn(position: Vec2, heading: f32) -> BoidSpawn {
+    BoidSpawn {
+        alignment: RuleParams::new(100., 0.),
+        cohesion: RuleParams::new(200., 0.),
+        ..BoidSpawn::new(position, heading)
+    }
+}
+
+fn heading(flock: &TestFlock, entity: Entity) -> Vec2 {
+    let transform = flock.world().get::<Transform>(entity).unwrap();
+    (transform.rotation * Vec3::Y).xy()
+}
+
+#[test]
+fn followers_fall_in_behind_the_leader() {
+    let mut flock = TestFlock::default();
+    let leader = flock.spawn(leader_spawn(Vec2::ZERO, 0.));
+    flock
+        .world_mut()
+        .entity_mut(leader)
+        .insert((Leader, LeaderScript::new(|_| Vec2::X)));
+    let follower = flock.spawn(BoidSpawn::new(Vec2::new(-200., 60.), 0.));
+
+    flock.step(180);
+
+    let world = flock.world();
+    let leader_position = world.get::<Transform>(leader).unwrap().translation.xy();
+    let follower_position = world.get::<Transform>(follower).unwrap().translation.xy();
+    let offset = follower_position - leader_position;
+    assert!(offset.x < 0., "follower at {offset} from the leader");
+    assert!(offset.length() < 300.);
+}
+
+#[test]
+fn followers_get_out_of_the_leaders_way() {
+    let mut flock = TestFlock::default();
+    let leader = flock.spawn(leader_spawn(Vec2::ZERO, 0.));
+    flock.world_mut().entity_mut(leader).insert(Leader);
+    let in_the_way = flock.spawn(BoidSpawn::new(Vec2::new(100., 5.), 0.));
+
+    flock.step(1);
+
+    let follow = flock.world().get::<FollowLeaderRule>(in_the_way).unwrap();
+    assert!(follow.velocity.y > 0.);
+    assert_eq!(follow.velocity.x, 0.);
+}
+
+#[test]
+fn followers_see_leaders_across_the_wrap_seam() {
+    let mut flock = TestFlock::default().with_bounds(WorldBounds::from_size(Vec2::splat(600.)));
+    let leader = flock.spawn(leader_spawn(Vec2::new(250., 0.), 0.));
+    flock.world_mut().entity_mut(leader).insert(Leader);
+    // 100 in front of the leader across the seam, 500 away without wrapping
+    let in_the_way = flock.spawn(BoidSpawn::new(Vec2::new(-250., 5.), 0.));
+
+    flock.step(1);
+
+    let follow = flock.world().get::<FollowLeaderRule>(in_the_way).unwrap();
+    assert!(follow.velocity.y > 0.);
+    assert_eq!(follow.velocity.x, 0.);
+}
+
+#[test]
+fn leaders_are_steered_by_script_and_keyboard() {
+    let mut flock = TestFlock::default();
+    let scripted = flock.spawn(leader_spawn(Vec2::new(0., -400.), 0.));
+    flock
+        .world_mut()
+        .entity_mut(scripted)
+        .insert((Leader, LeaderScript::new(|_| Vec2::NEG_Y)));
+    let keyboard = flock.spawn(leader_spawn(Vec2::new(0., 400.), 0.));
+    flock
+        .world_mut()
+        .entity_mut(keyboard)
+        .insert((Leader, KeyboardSteering::default()));
+
+    let mut keys = ButtonInput::<KeyCode>::default();
+    keys.press(KeyCode::ArrowLeft);
+    flock.world_mut().insert_resource(keys);
+
+    flock.step(30);
+
+    assert!(heading(&flock, scripted).y < -0.5);
+    assert!(heading(&flock, keyboard).y > 0.5);
+}
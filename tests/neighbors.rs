@@ -0,0 +1,227 @@
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use bevy::prelude::*;
+use boids_rs::{Arena, FieldOfView, Neighbor, NeighborIndex, Species};
+
+fn index_with(positions: &[Vec2], wrap: Option<Rect>) -> NeighborIndex {
+    let mut index = NeighborIndex::new(50.);
+    index.set_wrap(wrap.map(Arena::from));
+    for (i, position) in positions.iter().enumerate() {
+        index.insert(Neighbor {
+            entity: Entity::from_raw(i as u32),
+            position: position.extend(0.),
+            heading: Vec3::Y,
+            species: Species::default(),
+        });
+    }
+    index
+}
+
+fn found(index: &NeighborIndex, center: Vec2, radius: f32) -> Vec<(u32, Vec2)> {
+    let mut found: Vec<_> = index
+        .query(center.extend(0.), radius)
+        .map(|neighbor| (neighbor.entity.index(), neighbor.position.xy()))
+        .collect();
+    found.sort_by_key(|(index, _)| *index);
+    found
+}
+
+#[test]
+fn query_returns_boids_within_radius() {
+    let positions = [
+        Vec2::ZERO,
+        Vec2::new(30., 0.),
+        Vec2::new(0., -49.),
+        Vec2::new(80., 80.),
+    ];
+    let index = index_with(&positions, None);
+
+    let ids: Vec<u32> = found(&index, Vec2::ZERO, 50.)
+        .into_iter()
+        .map(|(id, _)| id)
+        .collect();
+
+    assert_eq!(ids, vec![0, 1, 2]);
+}
+
+#[test]
+fn query_does_not_cross_edges_without_wrap() {
+    let index = index_with(&[Vec2::new(-195., 0.)], None);
+
+    assert!(found(&index, Vec2::new(195., 0.), 20.).is_empty());
+}
+
+#[test]
+fn wrapped_query_sees_across_the_seam() {
+    let bounds = Rect::from_center_size(Vec2::ZERO, Vec2::splat(400.));
+    let index = index_with(&[Vec2::new(-195., 0.), Vec2::new(190., 195.)], Some(bounds));
+
+    assert_eq!(
+        found(&index, Vec2::new(195., 0.), 20.),
+        vec![(0, Vec2::new(205., 0.))]
+    );
+    assert_eq!(
+        found(&index, Vec2::new(190., -195.), 20.),
+        vec![(1, Vec2::new(190., -205.))]
+    );
+}
+
+#[test]
+fn wrapped_offset_takes_the_short_way_round() {
+    let bounds = Rect::from_center_size(Vec2::ZERO, Vec2::splat(200.));
+    let (from, to) = (Vec3::new(90., -90., 1.), Vec3::new(-90., 50., 3.));
+
+    assert_eq!(index_with(&[], None).offset(from, to), to - from);
+    // Z is unbounded in the plane and keeps its offset
+    assert_eq!(
+        index_with(&[], Some(bounds)).offset(from, to),
+        Vec3::new(20., -60., 2.)
+    );
+}
+
+#[test]
+fn visiting_boids_matches_the_query() {
+    let positions: Vec<Vec2> = (0..40)
+        .map(|i| Vec2::from_angle(i as f32 * 2.4) * (i as f32 * 4.))
+        .collect();
+    let bounds = Rect::from_center_size(Vec2::ZERO, Vec2::splat(200.));
+
+    for wrap in [None, Some(bounds)] {
+        let index = index_with(&positions, wrap);
+        for (center, radius) in [(Vec2::ZERO, 60.), (Vec2::new(90., -80.), 70.)] {
+            let mut visited = vec![];
+            index.for_each_within(center.extend(0.), radius, |neighbor| {
+                visited.push((neighbor.entity, neighbor.position));
+            });
+            let queried: Vec<_> = index
+                .query(center.extend(0.), radius)
+                .map(|neighbor| (neighbor.entity, neighbor.position))
+                .collect();
+
+            assert!(!visited.is_empty());
+            assert_eq!(visited, queried);
+        }
+    }
+}
+
+#[test]
+fn wrapped_query_sees_across_corners() {
+    let bounds = Rect::from_center_size(Vec2::ZERO, Vec2::splat(400.));
+    let index = index_with(&[Vec2::new(-198., -198.)], Some(bounds));
+
+    assert_eq!(
+        found(&index, Vec2::new(198., 198.), 10.),
+        vec![(0, Vec2::new(202., 202.))]
+    );
+}
+
+#[test]
+fn field_of_view_limits_by_angle_and_blind_spot() {
+    let heading = Vec3::Y;
+    let ahead = Vec3::new(0., 10., 0.);
+    let side = Vec3::new(10., 0., 0.);
+    let behind = Vec3::new(0., -10., 0.);
+
+    let all_around = FieldOfView::default();
+    assert!([ahead, side, behind]
+        .iter()
+        .all(|offset| all_around.contains(heading, *offset)));
+
+    let narrow = FieldOfView::new(FRAC_PI_2, 0.);
+    assert!(narrow.contains(heading, ahead));
+    assert!(!narrow.contains(heading, side));
+
+    let blind_spot = FieldOfView::new(TAU, FRAC_PI_2);
+    assert!(blind_spot.contains(heading, side));
+    assert!(!blind_spot.contains(heading, behind));
+
+    // in 3D the view is a cone around the heading
+    let above = Vec3::new(0., 0., 10.);
+    assert!(!narrow.contains(heading, above));
+    assert!(blind_spot.contains(heading, above));
+}
+
+#[test]
+fn nearest_returns_k_closest_in_order() {
+    let positions = [
+        Vec2::ZERO,
+        Vec2::new(300., 0.),
+        Vec2::new(0., 40.),
+        Vec2::new(-120., 0.),
+    ];
+    let index = index_with(&positions, None);
+    let ids = |neighbors: Vec<Neighbor>| -> Vec<u32> {
+        neighbors
+            .into_iter()
+            .map(|neighbor| neighbor.entity.index())
+            .collect()
+    };
+
+    assert_eq!(
+        ids(index.nearest(Vec3::ZERO, 3, f32::INFINITY, |_| true)),
+        vec![0, 2, 3]
+    );
+    assert_eq!(
+        ids(index.nearest(Vec3::ZERO, 3, 100., |n| n.entity.index() != 0)),
+        vec![2]
+    );
+    assert_eq!(
+        ids(index.nearest(Vec3::new(250., 0., 0.), 1, f32::INFINITY, |_| true)),
+        vec![1]
+    );
+}
+
+#[test]
+fn wrapped_nearest_counts_each_boid_once() {
+    let bounds = Rect::from_center_size(Vec2::ZERO, Vec2::splat(400.));
+    let positions = [
+        Vec2::new(10., 0.),
+        Vec2::new(-190., 0.),
+        Vec2::new(0., 150.),
+    ];
+    let index = index_with(&positions, Some(bounds));
+
+    let nearest = index.nearest(Vec3::new(190., 0., 0.), 5, f32::INFINITY, |_| true);
+    let found: Vec<(u32, Vec2)> = nearest
+        .iter()
+        .map(|neighbor| (neighbor.entity.index(), neighbor.position.xy()))
+        .collect();
+
+    // closest copies first, the boid across the seam is seen just beyond the edge
+    assert_eq!(
+        found,
+        vec![
+            (1, Vec2::new(210., 0.)),
+            (0, Vec2::new(10., 0.)),
+            (2, Vec2::new(0., 150.)),
+        ]
+    );
+}
+
+#[test]
+fn wrapped_query_sees_across_the_depth_of_a_volume() {
+    let arena = Arena {
+        min: Vec3::splat(-200.),
+        max: Vec3::splat(200.),
+    };
+    let mut index = NeighborIndex::new(50.);
+    index.set_wrap(Some(arena));
+    for (i, position) in [Vec3::new(0., 0., -195.), Vec3::new(0., 30., 0.)]
+        .into_iter()
+        .enumerate()
+    {
+        index.insert(Neighbor {
+            entity: Entity::from_raw(i as u32),
+            position,
+            heading: Vec3::Y,
+            species: Species::default(),
+        });
+    }
+
+    let above: Vec<Vec3> = index
+        .query(Vec3::new(0., 0., 195.), 20.)
+        .map(|neighbor| neighbor.position)
+        .collect();
+    assert_eq!(above, vec![Vec3::new(0., 0., 205.)]);
+    assert_eq!(index.query(Vec3::new(0., 0., 30.), 20.).count(), 0);
+}
@@ -0,0 +1,157 @@
+use bevy::prelude::*;
+use boids_rs::{
+    testing::TestFlock, BoidSpace, BoidSpawn, Obstacle, ObstacleAvoidanceRule, ObstacleShape,
+    BOID_SIZE,
+};
+
+#[test]
+fn closest_point_on_each_shape() {
+    let at_origin = Transform::default();
+
+    let circle = Obstacle::circle(10.);
+    assert_eq!(
+        circle.closest_point(&at_origin, Vec2::new(30., 0.)),
+        Some(Vec2::new(10., 0.))
+    );
+
+    let rectangle = Obstacle::rectangle(Vec2::new(20., 10.));
+    assert_eq!(
+        rectangle.closest_point(&at_origin, Vec2::new(30., 30.)),
+        Some(Vec2::new(10., 5.))
+    );
+    assert_eq!(
+        rectangle.closest_point(&at_origin, Vec2::new(1., 1.)),
+        Some(Vec2::new(1., 1.))
+    );
+
+    let triangle = Obstacle::polygon([
+        Vec2::new(-10., -10.),
+        Vec2::new(10., -10.),
+        Vec2::new(0., 10.),
+    ]);
+    assert_eq!(
+        triangle.closest_point(&at_origin, Vec2::new(0., -30.)),
+        Some(Vec2::new(0., -10.))
+    );
+    assert_eq!(
+        triangle.closest_point(&at_origin, Vec2::ZERO),
+        Some(Vec2::ZERO)
+    );
+
+    let moved = Transform::from_xyz(100., 0., 0.)
+        .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2));
+    let closest = rectangle
+        .closest_point(&moved, Vec2::new(100., 30.))
+        .unwrap();
+    assert!(closest.distance(Vec2::new(100., 10.)) < 1e-4, "{closest}");
+}
+
+#[test]
+#[should_panic(expected = "at least 3 vertices")]
+fn polygons_need_three_vertices() {
+    Obstacle::polygon([Vec2::ZERO, Vec2::X]);
+}
+
+#[test]
+fn degenerate_polygons_are_empty() {
+    for vertices in [vec![], vec![Vec2::ZERO], vec![Vec2::ZERO, Vec2::X]] {
+        let shape = ObstacleShape::Polygon(vertices);
+        assert_eq!(shape.closest_point(Vec2::new(0., 150.)), None);
+
+        let mut flock = TestFlock::default();
+        flock.world_mut().spawn(Obstacle { shape });
+        let boid = flock.spawn(BoidSpawn::new(Vec2::new(0., 150.), 0.));
+        flock.step(1);
+
+        let avoidance = flock.world().get::<ObstacleAvoidanceRule>(boid).unwrap();
+        assert_eq!(avoidance.velocity, Vec3::ZERO);
+    }
+}
+
+#[test]
+fn boids_steer_around_obstacles_ahead() {
+    for obstacle in [
+        Obstacle::circle(60.),
+        Obstacle::rectangle(Vec2::splat(120.)),
+        Obstacle::polygon([
+            Vec2::new(-60., -60.),
+            Vec2::new(60., -60.),
+            Vec2::new(0., 60.),
+        ]),
+    ] {
+        let mut flock = TestFlock::default();
+        let shape = obstacle.clone();
+        flock.world_mut().spawn(obstacle);
+        flock.spawn(BoidSpawn::new(Vec2::new(-400., 0.), 0.));
+
+        for _ in 0..300 {
+            flock.step(1);
+            let position = flock.positions()[0];
+            let surface = shape
+                .closest_point(&Transform::default(), position)
+                .unwrap();
+            assert!(
+                position.distance(surface) > BOID_SIZE / 2.,
+                "{:?} hit at {position}",
+                shape.shape
+            );
+        }
+    }
+}
+
+#[test]
+fn boids_fly_straight_without_obstacles_ahead() {
+    let mut flock = TestFlock::default();
+    flock
+        .world_mut()
+        .spawn((Obstacle::circle(60.), Transform::from_xyz(0., 300., 0.)));
+    flock.spawn(BoidSpawn::new(Vec2::new(-400., 0.), 0.));
+
+    flock.step(120);
+
+    assert!(flock.positions()[0].y.abs() < 1e-3);
+}
+
+#[test]
+fn climbing_boids_steer_around_columns() {
+    let mut flock = TestFlock::default();
+    flock
+        .world_mut()
+        .insert_resource(BoidSpace::Volume { depth: 4000. });
+    flock.world_mut().spawn(Obstacle::circle(60.));
+    flock.spawn(BoidSpawn {
+        pitch: 0.5,
+        ..BoidSpawn::new(Vec2::new(-300., 0.), 0.)
+    });
+
+    for _ in 0..240 {
+        flock.step(1);
+        let position = flock.positions()[0];
+        assert!(
+            position.length() > 60. + BOID_SIZE / 2.,
+            "hit at {position}"
+        );
+    }
+}
+
+#[test]
+fn columns_span_the_depth_of_the_box() {
+    let triangle = Obstacle::polygon([
+        Vec2::new(-10., -10.),
+        Vec2::new(10., -10.),
+        Vec2::new(0., 10.),
+    ]);
+
+    for shape in [Obstacle::circle(10.).shape, triangle.shape] {
+        let mesh = shape.column_mesh(300.);
+        let positions = mesh
+            .attribute(Mesh::ATTRIBUTE_POSITION)
+            .and_then(|positions| positions.as_float3())
+            .unwrap();
+        let depths = positions.iter().map(|position| position[2]);
+
+        assert_eq!(depths.clone().fold(f32::MAX, f32::min), -150.);
+        assert_eq!(depths.fold(f32::MIN, f32::max), 150.);
+        assert!(mesh.attribute(Mesh::ATTRIBUTE_NORMAL).is_some());
+    }
+}
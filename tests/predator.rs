@@ -0,0 +1,95 @@
+use bevy::prelude::*;
+use boids_rs::{
+    testing::TestFlock, BoidCaptured, BoidSpawn, FleeRule, HuntStrategy, Predator, PredatorBundle,
+    WorldBounds,
+};
+
+fn hunted_flock(boids: &[Vec2], predator: Vec2, strategy: HuntStrategy) -> (TestFlock, Entity) {
+    let mut flock = TestFlock::default();
+    for position in boids {
+        flock.spawn(BoidSpawn::new(*position, 0.));
+    }
+    let predator = flock
+        .world_mut()
+        .spawn(PredatorBundle::new(predator, 0., strategy))
+        .id();
+
+    (flock, predator)
+}
+
+#[test]
+fn boids_flee_predators_within_radius() {
+    let mut flock = TestFlock::default();
+    let near = flock.spawn(BoidSpawn::new(Vec2::new(100., 0.), 0.));
+    let far = flock.spawn(BoidSpawn::new(Vec2::new(-800., 0.), 0.));
+    flock
+        .world_mut()
+        .spawn(PredatorBundle::new(Vec2::ZERO, 0., HuntStrategy::Nearest));
+
+    flock.step(1);
+
+    let world = flock.world();
+    assert!(world.get::<FleeRule>(near).unwrap().velocity.x > 0.);
+    assert_eq!(world.get::<FleeRule>(far).unwrap().velocity, Vec3::ZERO);
+}
+
+#[test]
+fn predators_pick_prey_by_strategy() {
+    let boids = [
+        Vec2::new(100., 0.),
+        Vec2::new(130., 0.),
+        Vec2::new(100., 30.),
+        Vec2::new(-400., 0.),
+    ];
+
+    let (mut flock, predator) = hunted_flock(&boids, Vec2::ZERO, HuntStrategy::Nearest);
+    flock.step(1);
+    let target = flock.world().get::<Predator>(predator).unwrap().target;
+    let position = flock.world().get::<Transform>(target.unwrap()).unwrap();
+    assert!(position.translation.xy().distance(boids[0]) < 10.);
+
+    let (mut flock, predator) = hunted_flock(&boids, Vec2::ZERO, HuntStrategy::MostIsolated);
+    flock.step(1);
+    let target = flock.world().get::<Predator>(predator).unwrap().target;
+    let position = flock.world().get::<Transform>(target.unwrap()).unwrap();
+    assert!(position.translation.xy().distance(boids[3]) < 10.);
+}
+
+#[test]
+fn caught_boids_are_reported_and_despawned() {
+    let (mut flock, predator) =
+        hunted_flock(&[Vec2::new(10., 0.)], Vec2::ZERO, HuntStrategy::Nearest);
+
+    flock.step(1);
+
+    let captures: Vec<BoidCaptured> = flock
+        .world()
+        .resource::<Events<BoidCaptured>>()
+        .iter_current_update_events()
+        .copied()
+        .collect();
+    assert_eq!(captures.len(), 1);
+    assert_eq!(captures[0].predator, predator);
+    assert!(flock.world().get_entity(captures[0].prey).is_err());
+    assert!(flock.positions().is_empty());
+}
+
+#[test]
+fn predators_reach_across_the_wrap_seam() {
+    let bounds = WorldBounds::from_size(Vec2::splat(400.));
+    let mut flock = TestFlock::default().with_bounds(bounds);
+    let fleeing = flock.spawn(BoidSpawn::new(Vec2::new(100., 150.), 0.));
+    let caught = flock.spawn(BoidSpawn::new(Vec2::new(195., -150.), 0.));
+    for position in [Vec2::new(-150., 150.), Vec2::new(-195., -150.)] {
+        flock
+            .world_mut()
+            .spawn(PredatorBundle::new(position, 0., HuntStrategy::Nearest));
+    }
+
+    flock.step(1);
+
+    // the first predator is 150 away across the seam, on the boid's right
+    let world = flock.world();
+    assert!(world.get::<FleeRule>(fleeing).unwrap().velocity.x < 0.);
+    assert!(world.get_entity(caught).is_err());
+}
@@ -0,0 +1,79 @@
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+use bevy::prelude::*;
+use boids_rs::{
+    testing::{separation_only, TestFlock},
+    turn_towards, Attractor, BoidSpace, BoidSpawn, Falloff, WorldBounds,
+};
+
+#[test]
+fn turning_stays_in_the_plane_even_when_reversing() {
+    let rotation = Quat::IDENTITY;
+
+    let half_turn = turn_towards(rotation, Vec3::NEG_Y, FRAC_PI_2);
+    let forward = half_turn * Vec3::Y;
+    assert!(forward.abs_diff_eq(Vec3::NEG_X, 1e-5) || forward.abs_diff_eq(Vec3::X, 1e-5));
+
+    let climb = turn_towards(rotation, Vec3::new(0., 1., 1.), PI);
+    assert!((climb * Vec3::Y).abs_diff_eq(Vec3::new(0., 1., 1.).normalize(), 1e-5));
+}
+
+#[test]
+fn planar_flocks_stay_in_the_plane() {
+    let mut flock = TestFlock::default();
+    flock.world_mut().spawn((
+        Attractor::new(2., 1000., Falloff::Constant),
+        Transform::from_xyz(0., 0., 300.),
+    ));
+    flock.spawn_random(24, Rect::from_center_size(Vec2::ZERO, Vec2::splat(300.)));
+    let depths: Vec<f32> = flock.translations().iter().map(|t| t.z).collect();
+
+    flock.step(60);
+
+    let after: Vec<f32> = flock.translations().iter().map(|t| t.z).collect();
+    assert_eq!(after, depths);
+}
+
+#[test]
+fn volume_flocks_separate_along_z() {
+    let mut flock = TestFlock::default();
+    flock
+        .world_mut()
+        .insert_resource(BoidSpace::Volume { depth: 800. });
+    for i in 0..5 {
+        flock.spawn(separation_only(BoidSpawn {
+            z: i as f32 * 10. - 20.,
+            ..BoidSpawn::new(Vec2::ZERO, 0.)
+        }));
+    }
+
+    flock.step(90);
+
+    let depths: Vec<f32> = flock.translations().iter().map(|t| t.z).collect();
+    let spread = depths.iter().copied().fold(f32::MIN, f32::max)
+        - depths.iter().copied().fold(f32::MAX, f32::min);
+    assert!(spread > 100., "depths {depths:?}");
+}
+
+#[test]
+fn wrapping_keeps_boids_inside_the_box() {
+    let space = BoidSpace::Volume { depth: 400. };
+    let mut flock = TestFlock::default().with_bounds(WorldBounds::from_size(Vec2::splat(400.)));
+    flock.world_mut().insert_resource(space);
+    let arena = space.arena(flock.bounds());
+    for i in 0..16 {
+        let angle = i as f32 * 0.7;
+        flock.spawn(BoidSpawn {
+            z: (i as f32 - 8.) * 20.,
+            pitch: FRAC_PI_4 * angle.sin(),
+            ..BoidSpawn::new(Vec2::from_angle(angle) * 150., angle)
+        });
+    }
+
+    for _ in 0..10 {
+        flock.step(30);
+        for translation in flock.translations() {
+            assert!(arena.contains(translation), "{translation} left {arena:?}");
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+use boids_rs::{
+    testing::{alignment_only, heading_variance, TestFlock},
+    BoidSpawn, Species, SpeciesInteraction, SpeciesInteractions,
+};
+
+#[test]
+fn species_flock_with_their_own_kind_unless_set() {
+    let mut interactions = SpeciesInteractions::kin_only();
+    interactions.set(Species(0), Species(1), SpeciesInteraction::IGNORE);
+
+    assert_eq!(
+        interactions.get(Species(2), Species(2)),
+        SpeciesInteraction::FLOCK
+    );
+    assert_eq!(
+        interactions.get(Species(1), Species(2)),
+        SpeciesInteraction::SEPARATE
+    );
+    assert_eq!(
+        interactions.get(Species(0), Species(1)),
+        SpeciesInteraction::IGNORE
+    );
+    assert_eq!(
+        interactions.get(Species(1), Species(0)),
+        SpeciesInteraction::SEPARATE
+    );
+}
+
+fn headings_after(interactions: SpeciesInteractions) -> Vec<Vec2> {
+    let mut flock = TestFlock::default();
+    flock.world_mut().insert_resource(interactions);
+    flock.spawn(alignment_only(BoidSpawn {
+        species: Species(0),
+        ..BoidSpawn::new(Vec2::new(-30., 0.), 0.)
+    }));
+    flock.spawn(alignment_only(BoidSpawn {
+        species: Species(1),
+        ..BoidSpawn::new(Vec2::new(30., 0.), FRAC_PI_2)
+    }));
+
+    flock.step(60);
+    flock.headings()
+}
+
+#[test]
+fn species_only_align_where_the_matrix_allows() {
+    let initial = heading_variance(&[Vec2::X, Vec2::Y]);
+
+    let mixed = headings_after(SpeciesInteractions::default());
+    assert!(heading_variance(&mixed) < initial * 0.5);
+
+    let apart = headings_after(SpeciesInteractions::kin_only());
+    assert!((heading_variance(&apart) - initial).abs() < 1e-3);
+}
+
+#[test]
+fn negative_weights_count_as_ignoring() {
+    assert_eq!(
+        SpeciesInteraction::new(-1., 0.5, f32::NAN),
+        SpeciesInteraction::new(0., 0.5, 0.)
+    );
+
+    // set directly, the opposite weights must not cancel out into an infinite pull
+    let mut interactions = SpeciesInteractions::default();
+    let mut mixed = SpeciesInteraction::FLOCK;
+    mixed.cohesion = -1.;
+    interactions.set(Species(0), Species(1), mixed);
+
+    let mut flock = TestFlock::default();
+    flock.world_mut().insert_resource(interactions);
+    for (i, species) in [0, 0, 1].into_iter().enumerate() {
+        flock.spawn(BoidSpawn {
+            species: Species(species),
+            ..BoidSpawn::new(Vec2::new(i as f32 * 40., 0.), 0.)
+        });
+    }
+
+    flock.step(30);
+
+    assert!(flock.transforms().iter().all(Transform::is_finite));
+}
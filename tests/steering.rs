@@ -0,0 +1,50 @@
+use bevy::prelude::*;
+use boids_rs::{
+    testing::TestFlock, BoidMovement, BoidSpawn, Neighbor, SteeringRule, SteeringRuleAppExt,
+    SteeringVelocities,
+};
+
+// steers every boid straight up
+#[derive(Component)]
+struct Climb;
+
+impl SteeringRule for Climb {
+    fn radius(&self) -> f32 {
+        100.
+    }
+
+    fn steer(&mut self, _: &Neighbor, _: &BoidMovement, _: &[Neighbor]) -> Vec3 {
+        Vec3::Y * 100.
+    }
+}
+
+#[test]
+fn custom_rules_steer_boids() {
+    let mut flock = TestFlock::default();
+    flock.app.add_steering_rule::<Climb>();
+    let boid = flock.spawn(BoidSpawn::new(Vec2::ZERO, 0.));
+    flock.world_mut().entity_mut(boid).insert(Climb);
+
+    flock.step(60);
+
+    assert!(flock.headings()[0].y > 0.9);
+}
+
+#[test]
+fn custom_rule_velocities_are_cleared_without_the_built_in_rules() {
+    let mut flock = TestFlock::default();
+    flock.app.add_steering_rule::<Climb>();
+    let agent = flock
+        .world_mut()
+        .spawn((
+            Transform::default(),
+            BoidMovement::new(0, 100., Vec3::X, 1.),
+            Climb,
+        ))
+        .id();
+
+    flock.step(10);
+
+    let velocities = flock.world().get::<SteeringVelocities>(agent).unwrap();
+    assert!(velocities.0.is_empty());
+}
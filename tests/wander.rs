@@ -0,0 +1,44 @@
+use bevy::prelude::*;
+use boids_rs::{perlin_noise, testing::TestFlock, BoidSpawn};
+
+#[test]
+fn perlin_noise_is_smooth_and_seeded() {
+    let samples: Vec<f32> = (0..2000)
+        .map(|i| perlin_noise(7, i as f32 * 0.01))
+        .collect();
+
+    assert!(samples.iter().all(|value| (-1. ..=1.).contains(value)));
+    assert!(samples
+        .windows(2)
+        .all(|pair| (pair[1] - pair[0]).abs() < 0.1));
+    assert!(samples.iter().any(|value| value.abs() > 0.3));
+
+    assert_eq!(perlin_noise(7, 3.3), samples[330]);
+    assert_ne!(perlin_noise(8, 3.3), samples[330]);
+}
+
+fn wandering_path(seed: u64) -> Vec<Vec2> {
+    let mut flock = TestFlock::default();
+    flock.spawn(BoidSpawn {
+        wander_strength: 0.5,
+        wander_rate: 1.,
+        wander_seed: seed,
+        ..BoidSpawn::new(Vec2::ZERO, 0.)
+    });
+
+    (0..10)
+        .map(|_| {
+            flock.step(30);
+            flock.positions()[0]
+        })
+        .collect()
+}
+
+#[test]
+fn wandering_boids_leave_the_straight_line() {
+    let path = wandering_path(1);
+
+    assert!(path.iter().any(|position| position.y.abs() > 20.));
+    assert_eq!(path, wandering_path(1));
+    assert_ne!(path, wandering_path(2));
+}